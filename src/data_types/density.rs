@@ -0,0 +1,178 @@
+use std::{collections::HashMap, sync::OnceLock};
+
+use crate::{round_to_unit, Alternative, MeasureType, MixedRational, ParsedRecipe, RecipeItem, Unit};
+
+// Grams per milliliter, as hundredths
+#[rustfmt::skip]
+const BUNDLED_DENSITIES: &[(&str, i64)] = &[
+    ("water", 100), ("milk", 103), ("buttermilk", 103), ("heavy cream", 101), ("sour cream", 102),
+    ("yogurt", 103), ("cream cheese", 97), ("butter", 96), ("oil", 92), ("olive oil", 91),
+    ("honey", 142), ("maple syrup", 132), ("molasses", 140), ("corn syrup", 138),
+    ("flour", 53), ("all purpose flour", 53), ("bread flour", 55), ("whole wheat flour", 48),
+    ("cake flour", 42), ("almond flour", 41), ("cornstarch", 54), ("cocoa powder", 42),
+    ("sugar", 85), ("granulated sugar", 85), ("brown sugar", 93), ("powdered sugar", 51),
+    ("salt", 122), ("kosher salt", 64), ("baking soda", 93), ("baking powder", 82),
+    ("rice", 78), ("oats", 38), ("rolled oats", 38), ("chocolate chips", 71),
+    ("peanut butter", 109), ("parmesan", 42), ("shredded cheese", 47), ("grated carrot", 47),
+    ("vanilla extract", 88), ("lemon juice", 103), ("soy sauce", 115), ("garlic", 57),
+];
+
+/// Grams per milliliter of ingredients, keyed by normalized ingredient name
+#[derive(Clone, Debug, Default)]
+pub struct DensityTable {
+    pub densities: HashMap<String, MixedRational>,
+}
+
+impl DensityTable {
+    pub fn bundled() -> Self {
+        let mut table = DensityTable::default();
+        for (name, hundredths) in BUNDLED_DENSITIES {
+            table.insert(name, MixedRational::fract(*hundredths, 100));
+        }
+        table
+    }
+    /// The bundled table, built once and shared
+    pub fn shared() -> &'static Self {
+        static TABLE: OnceLock<DensityTable> = OnceLock::new();
+        TABLE.get_or_init(DensityTable::bundled)
+    }
+    pub fn insert(&mut self, name: &str, grams_per_ml: MixedRational) {
+        self.densities.insert(normalize_ingredient_name(name), grams_per_ml);
+    }
+    /// Add entries from a JSON object of name to grams per milliliter, ex: {"tahini": "0.96"}
+    pub fn extend_from_file<P>(&mut self, path: P) -> Result<(), Box<dyn std::error::Error>>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let entries: HashMap<String, String> =
+            serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        for (name, density) in entries {
+            self.insert(&name, density.parse::<MixedRational>()?);
+        }
+        Ok(())
+    }
+    /// Finds the most specific entry whose words all appear in `ingredient` and that names the same
+    /// thing, "rice vinegar" is a vinegar and not rice
+    pub fn lookup(&self, ingredient: &str) -> Option<MixedRational> {
+        let name = normalize_ingredient_name(ingredient);
+        if let Some(d) = self.densities.get(&name) {
+            return Some(*d);
+        }
+        let words = name.split(' ').collect::<Vec<_>>();
+        self.densities
+            .iter()
+            .filter(|(key, _)| key.split(' ').next_back() == words.last().copied())
+            .filter(|(key, _)| key.split(' ').all(|k| words.contains(&k)))
+            .max_by_key(|(key, _)| (key.split(' ').count(), key.len(), key.as_str()))
+            .map(|(_, d)| *d)
+    }
+}
+
+/// Lowercase, letters only and singular, ex: "Brown Sugars," -> "brown sugar"
+pub fn normalize_ingredient_name(name: &str) -> String {
+    name.to_lowercase()
+        .replace(|c: char| !c.is_alphabetic() && c != ' ', " ")
+        .split_whitespace()
+        .map(|w| {
+            if w.len() > 3 && w.ends_with('s') && !w.ends_with("ss") {
+                &w[..w.len() - 1]
+            } else {
+                w
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl RecipeItem {
+    /// Convert into `unit`, looking up the density when going between volume and mass
+    pub fn convert_to(&self, unit: Unit) -> Option<Self> {
+        self.convert_to_with(unit, DensityTable::shared())
+    }
+    pub fn convert_to_with(&self, unit: Unit, densities: &DensityTable) -> Option<Self> {
+        let density = densities.lookup(&self.name);
+        // Densities are rough, so the result doesn't need to be an exact fraction
+        let convert = |m: MeasureType| {
+            m.convert_with_density(unit, density).map(|mut c| {
+                c.count = round_to_unit(c.count, unit);
+                c
+            })
+        };
+        // Containers convert by what they hold, ex: 2 (14.5 oz) cans -> 822 g
+        if let (Some(contents), Some(size)) = (self.contents(), self.container_size) {
+            let measure_b = match self.measure_b {
+                Some(b) => Some(convert(MeasureType { count: size.count * b.count, unit: size.unit })?),
+                None => None,
+            };
+            return Some(RecipeItem {
+                measure: convert(contents)?,
+                measure_b,
+                container_size: None,
+                alternatives: self.converted_alternatives(unit, densities),
+                ..self.clone()
+            });
+        }
+        let measure = convert(self.measure)?;
+        let measure_b = match self.measure_b {
+            Some(b) => Some(convert(b)?),
+            None => None,
+        };
+        Some(RecipeItem {
+            measure,
+            measure_b,
+            alternatives: self.converted_alternatives(unit, densities),
+            ..self.clone()
+        })
+    }
+    // Alternatives with their own amount convert by their own density, or stay as written
+    fn converted_alternatives(&self, unit: Unit, densities: &DensityTable) -> Vec<Alternative> {
+        self.alternatives
+            .iter()
+            .map(|a| Alternative {
+                name: a.name.clone(),
+                measure: a.measure.map(|m| {
+                    m.convert_with_density(unit, densities.lookup(&a.name)).map_or(m, |mut c| {
+                        c.count = round_to_unit(c.count, unit);
+                        c
+                    })
+                }),
+            })
+            .collect()
+    }
+}
+
+impl ParsedRecipe {
+    /// Convert every ingredient that can be into `unit`, the rest are left untouched
+    pub fn convert_ingredients(&self, unit: Unit, densities: &DensityTable) -> Self {
+        let mut new = self.clone();
+        for item in new.data.ingredients.iter_mut() {
+            if let Some(converted) = item.convert_to_with(unit, densities) {
+                *item = converted;
+            }
+        }
+        new
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_matches_the_head_noun() {
+        let table = DensityTable::bundled();
+        assert_eq!(table.lookup("rice vinegar"), None);
+        assert_eq!(table.lookup("light brown sugars"), Some(MixedRational::fract(93, 100)));
+        assert_eq!(table.lookup("extra virgin olive oil"), Some(MixedRational::fract(91, 100)));
+    }
+
+    #[test]
+    fn grams_are_rounded() {
+        let flour = RecipeItem::parse("1 cup flour", None).unwrap().convert_to(Unit::Gram).unwrap();
+        assert_eq!(flour.to_string().trim(), "125 grams flour");
+        let honey = RecipeItem::parse("1 tsp honey", None).unwrap().convert_to(Unit::Gram).unwrap();
+        assert_eq!(honey.measure.count, MixedRational::whole(7));
+        let cups = RecipeItem::parse("100 g sugar", None).unwrap().convert_to(Unit::Cup).unwrap();
+        assert!(cups.measure.count.den <= 100);
+    }
+}
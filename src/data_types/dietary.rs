@@ -0,0 +1,433 @@
+use serde::de::Visitor;
+use std::{collections::HashMap, fmt, str::FromStr, sync::OnceLock};
+
+use crate::{normalize_ingredient_name, ParsedRecipe, RecipeItem};
+
+/// Something an ingredient is or contains that a diet or allergy rules out
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FoodGroup {
+    Meat,
+    Fish,
+    Shellfish,
+    Dairy,
+    Egg,
+    Gluten,
+    Peanut,
+    TreeNut,
+    Soy,
+    Sesame,
+    Honey,
+    Gelatin,
+}
+
+impl FoodGroup {
+    pub const ALLERGENS: [FoodGroup; 9] = [
+        FoodGroup::Dairy,
+        FoodGroup::Egg,
+        FoodGroup::Fish,
+        FoodGroup::Shellfish,
+        FoodGroup::TreeNut,
+        FoodGroup::Peanut,
+        FoodGroup::Gluten,
+        FoodGroup::Soy,
+        FoodGroup::Sesame,
+    ];
+    const ANIMAL: [FoodGroup; 7] = [
+        FoodGroup::Meat,
+        FoodGroup::Fish,
+        FoodGroup::Shellfish,
+        FoodGroup::Dairy,
+        FoodGroup::Egg,
+        FoodGroup::Honey,
+        FoodGroup::Gelatin,
+    ];
+    pub fn is_allergen(&self) -> bool {
+        FoodGroup::ALLERGENS.contains(self)
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            FoodGroup::Meat => "meat",
+            FoodGroup::Fish => "fish",
+            FoodGroup::Shellfish => "shellfish",
+            FoodGroup::Dairy => "dairy",
+            FoodGroup::Egg => "egg",
+            FoodGroup::Gluten => "gluten",
+            FoodGroup::Peanut => "peanut",
+            FoodGroup::TreeNut => "tree-nut",
+            FoodGroup::Soy => "soy",
+            FoodGroup::Sesame => "sesame",
+            FoodGroup::Honey => "honey",
+            FoodGroup::Gelatin => "gelatin",
+        }
+    }
+}
+
+/// What a recipe is suitable for, ex: vegan, gluten-free, or an allergen it has, ex: contains-shellfish
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DietaryTag {
+    Vegetarian,
+    Vegan,
+    Pescatarian,
+    GlutenFree,
+    DairyFree,
+    EggFree,
+    NutFree,
+    SoyFree,
+    Contains(FoodGroup),
+}
+
+impl DietaryTag {
+    // Tags a recipe earns by not having any of these
+    #[rustfmt::skip]
+    const FREE_OF: &'static [(DietaryTag, &'static [FoodGroup])] = &[
+        (DietaryTag::Vegetarian, &[FoodGroup::Meat, FoodGroup::Fish, FoodGroup::Shellfish, FoodGroup::Gelatin]),
+        (DietaryTag::Vegan, &FoodGroup::ANIMAL),
+        (DietaryTag::Pescatarian, &[FoodGroup::Meat, FoodGroup::Gelatin]),
+        (DietaryTag::GlutenFree, &[FoodGroup::Gluten]),
+        (DietaryTag::DairyFree, &[FoodGroup::Dairy]),
+        (DietaryTag::EggFree, &[FoodGroup::Egg]),
+        (DietaryTag::NutFree, &[FoodGroup::Peanut, FoodGroup::TreeNut]),
+        (DietaryTag::SoyFree, &[FoodGroup::Soy]),
+    ];
+    /// What a recipe can't have to be this, nothing for a contains tag
+    pub fn excluded_groups(&self) -> Vec<FoodGroup> {
+        DietaryTag::FREE_OF
+            .iter()
+            .find(|(tag, _)| tag == self)
+            .map_or(Vec::new(), |(_, groups)| groups.to_vec())
+    }
+    /// Every tag that applies to food with these groups in it
+    pub fn from_groups(groups: &[FoodGroup]) -> Vec<DietaryTag> {
+        let mut tags = DietaryTag::FREE_OF
+            .iter()
+            .filter(|(_, excluded)| !excluded.iter().any(|g| groups.contains(g)))
+            .map(|(tag, _)| *tag)
+            .collect::<Vec<_>>();
+        tags.extend(
+            FoodGroup::ALLERGENS
+                .iter()
+                .filter(|g| groups.contains(g))
+                .map(|g| DietaryTag::Contains(*g)),
+        );
+        tags
+    }
+}
+
+impl fmt::Display for DietaryTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DietaryTag::Vegetarian => write!(f, "vegetarian"),
+            DietaryTag::Vegan => write!(f, "vegan"),
+            DietaryTag::Pescatarian => write!(f, "pescatarian"),
+            DietaryTag::GlutenFree => write!(f, "gluten-free"),
+            DietaryTag::DairyFree => write!(f, "dairy-free"),
+            DietaryTag::EggFree => write!(f, "egg-free"),
+            DietaryTag::NutFree => write!(f, "nut-free"),
+            DietaryTag::SoyFree => write!(f, "soy-free"),
+            DietaryTag::Contains(group) => write!(f, "contains-{}", group.name()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDietaryTagError(pub String);
+
+impl fmt::Display for ParseDietaryTagError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown dietary tag \"{}\"", self.0)
+    }
+}
+
+impl std::error::Error for ParseDietaryTagError {}
+
+impl FromStr for DietaryTag {
+    type Err = ParseDietaryTagError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.trim().to_lowercase().replace([' ', '_'], "-");
+        if let Some(group) = lower.strip_prefix("contains-") {
+            return FoodGroup::ALLERGENS
+                .into_iter()
+                .find(|g| g.name() == group)
+                .map(DietaryTag::Contains)
+                .ok_or_else(|| ParseDietaryTagError(s.into()));
+        }
+        DietaryTag::FREE_OF
+            .iter()
+            .map(|(tag, _)| *tag)
+            .find(|tag| tag.to_string() == lower)
+            .ok_or_else(|| ParseDietaryTagError(s.into()))
+    }
+}
+
+impl serde::Serialize for DietaryTag {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+pub struct DietaryTagVisitor;
+impl<'de> Visitor<'de> for DietaryTagVisitor {
+    type Value = DietaryTag;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "A dietary tag such as \"vegan\" or \"contains-soy\"")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        v.parse().map_err(E::custom)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for DietaryTag {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_str(DietaryTagVisitor)
+    }
+}
+
+#[rustfmt::skip]
+const BUNDLED_CONTAINS: &[(&str, &[FoodGroup])] = &[
+    ("beef", &[FoodGroup::Meat]), ("pork", &[FoodGroup::Meat]), ("bacon", &[FoodGroup::Meat]),
+    ("ham", &[FoodGroup::Meat]), ("sausage", &[FoodGroup::Meat]), ("chicken", &[FoodGroup::Meat]),
+    ("turkey", &[FoodGroup::Meat]), ("lamb", &[FoodGroup::Meat]), ("veal", &[FoodGroup::Meat]),
+    ("duck", &[FoodGroup::Meat]), ("venison", &[FoodGroup::Meat]), ("prosciutto", &[FoodGroup::Meat]),
+    ("pancetta", &[FoodGroup::Meat]), ("salami", &[FoodGroup::Meat]), ("pepperoni", &[FoodGroup::Meat]),
+    ("chorizo", &[FoodGroup::Meat]), ("steak", &[FoodGroup::Meat]), ("meat", &[FoodGroup::Meat]),
+    ("lard", &[FoodGroup::Meat]), ("gelatin", &[FoodGroup::Gelatin]), ("honey", &[FoodGroup::Honey]),
+    ("fish", &[FoodGroup::Fish]), ("salmon", &[FoodGroup::Fish]), ("tuna", &[FoodGroup::Fish]),
+    ("cod", &[FoodGroup::Fish]), ("tilapia", &[FoodGroup::Fish]), ("halibut", &[FoodGroup::Fish]),
+    ("trout", &[FoodGroup::Fish]), ("sardines", &[FoodGroup::Fish]), ("anchovies", &[FoodGroup::Fish]),
+    ("anchovy", &[FoodGroup::Fish]), ("mackerel", &[FoodGroup::Fish]), ("haddock", &[FoodGroup::Fish]),
+    ("worcestershire", &[FoodGroup::Fish]),
+    ("shrimp", &[FoodGroup::Shellfish]), ("prawns", &[FoodGroup::Shellfish]), ("crab", &[FoodGroup::Shellfish]),
+    ("lobster", &[FoodGroup::Shellfish]), ("clams", &[FoodGroup::Shellfish]), ("mussels", &[FoodGroup::Shellfish]),
+    ("oysters", &[FoodGroup::Shellfish]), ("scallops", &[FoodGroup::Shellfish]), ("crawfish", &[FoodGroup::Shellfish]),
+    ("milk", &[FoodGroup::Dairy]), ("buttermilk", &[FoodGroup::Dairy]), ("butter", &[FoodGroup::Dairy]),
+    ("cream", &[FoodGroup::Dairy]), ("cheese", &[FoodGroup::Dairy]), ("parmesan", &[FoodGroup::Dairy]),
+    ("mozzarella", &[FoodGroup::Dairy]), ("cheddar", &[FoodGroup::Dairy]), ("ricotta", &[FoodGroup::Dairy]),
+    ("feta", &[FoodGroup::Dairy]), ("yogurt", &[FoodGroup::Dairy]), ("ghee", &[FoodGroup::Dairy]),
+    ("whey", &[FoodGroup::Dairy]), ("half and half", &[FoodGroup::Dairy]),
+    ("eggs", &[FoodGroup::Egg]), ("mayonnaise", &[FoodGroup::Egg]), ("mayo", &[FoodGroup::Egg]),
+    ("meringue", &[FoodGroup::Egg]),
+    ("flour", &[FoodGroup::Gluten]), ("wheat", &[FoodGroup::Gluten]), ("bread", &[FoodGroup::Gluten]),
+    ("breadcrumbs", &[FoodGroup::Gluten]), ("panko", &[FoodGroup::Gluten]), ("pasta", &[FoodGroup::Gluten]),
+    ("spaghetti", &[FoodGroup::Gluten]), ("noodles", &[FoodGroup::Gluten]), ("barley", &[FoodGroup::Gluten]),
+    ("rye", &[FoodGroup::Gluten]), ("couscous", &[FoodGroup::Gluten]), ("semolina", &[FoodGroup::Gluten]),
+    ("crackers", &[FoodGroup::Gluten]), ("tortillas", &[FoodGroup::Gluten]), ("beer", &[FoodGroup::Gluten]),
+    ("pastry", &[FoodGroup::Gluten]), ("croutons", &[FoodGroup::Gluten]), ("buns", &[FoodGroup::Gluten]),
+    ("peanuts", &[FoodGroup::Peanut]), ("peanut butter", &[FoodGroup::Peanut]),
+    ("almonds", &[FoodGroup::TreeNut]), ("walnuts", &[FoodGroup::TreeNut]), ("pecans", &[FoodGroup::TreeNut]),
+    ("cashews", &[FoodGroup::TreeNut]), ("pistachios", &[FoodGroup::TreeNut]), ("hazelnuts", &[FoodGroup::TreeNut]),
+    ("macadamia", &[FoodGroup::TreeNut]), ("pine nuts", &[FoodGroup::TreeNut]), ("nuts", &[FoodGroup::TreeNut]),
+    ("almond milk", &[FoodGroup::TreeNut]), ("almond flour", &[FoodGroup::TreeNut]), ("almond butter", &[FoodGroup::TreeNut]),
+    ("soy", &[FoodGroup::Soy]), ("soybeans", &[FoodGroup::Soy]), ("tofu", &[FoodGroup::Soy]),
+    ("edamame", &[FoodGroup::Soy]), ("miso", &[FoodGroup::Soy]), ("tempeh", &[FoodGroup::Soy]),
+    ("tamari", &[FoodGroup::Soy]), ("soy sauce", &[FoodGroup::Soy, FoodGroup::Gluten]), ("soy milk", &[FoodGroup::Soy]),
+    ("sesame", &[FoodGroup::Sesame]), ("tahini", &[FoodGroup::Sesame]), ("hummus", &[FoodGroup::Sesame]),
+    ("imitation crab", &[FoodGroup::Fish]),
+    // Not what their last word makes them sound like
+    ("coconut milk", &[]), ("coconut cream", &[]), ("oat milk", &[]), ("rice milk", &[]),
+    ("cocoa butter", &[]), ("cream of tartar", &[]), ("rice flour", &[]), ("coconut flour", &[]),
+    ("corn tortillas", &[]), ("rice noodles", &[]), ("nutritional yeast", &[]),
+];
+
+// Words saying an ingredient is a version without something, ex: "vegan butter", "gluten-free flour"
+#[rustfmt::skip]
+const BUNDLED_FREE_OF: &[(&str, &[FoodGroup])] = &[
+    ("vegan", &FoodGroup::ANIMAL), ("plant based", &FoodGroup::ANIMAL), ("dairy free", &[FoodGroup::Dairy]),
+    ("non dairy", &[FoodGroup::Dairy]), ("lactose free", &[]), ("gluten free", &[FoodGroup::Gluten]),
+    ("egg free", &[FoodGroup::Egg]), ("nut free", &[FoodGroup::Peanut, FoodGroup::TreeNut]),
+    ("soy free", &[FoodGroup::Soy]), ("meatless", &[FoodGroup::Meat]), ("imitation", &[FoodGroup::Shellfish]),
+];
+
+/// A phrase as written in the rules and the food groups it means
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DietaryRule {
+    pub phrase: String,
+    pub groups: Vec<FoodGroup>,
+}
+
+/// Which food groups ingredient names put a recipe in, keyed by normalized phrase
+#[derive(Clone, Debug, Default)]
+pub struct DietaryRules {
+    pub contains: HashMap<String, DietaryRule>,
+    /// Phrases that take groups back out, ex: "vegan" in "vegan butter"
+    pub free_of: HashMap<String, DietaryRule>,
+}
+
+#[derive(serde::Deserialize)]
+struct DietaryRulesFile {
+    #[serde(default)]
+    contains: HashMap<String, Vec<FoodGroup>>,
+    #[serde(default)]
+    free_of: HashMap<String, Vec<FoodGroup>>,
+}
+
+impl DietaryRules {
+    pub fn bundled() -> Self {
+        let mut rules = DietaryRules::default();
+        for (phrase, groups) in BUNDLED_CONTAINS {
+            rules.insert(phrase, groups);
+        }
+        for (phrase, groups) in BUNDLED_FREE_OF {
+            rules.insert_free_of(phrase, groups);
+        }
+        rules
+    }
+    /// The bundled rules, built once and shared
+    pub fn shared() -> &'static Self {
+        static RULES: OnceLock<DietaryRules> = OnceLock::new();
+        RULES.get_or_init(DietaryRules::bundled)
+    }
+    /// An empty `groups` marks a phrase as safe, ex: "coconut milk" isn't dairy
+    pub fn insert(&mut self, phrase: &str, groups: &[FoodGroup]) {
+        self.contains.insert(normalize_ingredient_name(phrase), DietaryRule::new(phrase, groups));
+    }
+    pub fn insert_free_of(&mut self, phrase: &str, groups: &[FoodGroup]) {
+        self.free_of.insert(normalize_ingredient_name(phrase), DietaryRule::new(phrase, groups));
+    }
+    /// Add rules from a JSON object, ex: {"contains": {"quorn": ["egg"]}, "free_of": {"eggless": ["egg"]}}
+    pub fn extend_from_file<P>(&mut self, path: P) -> Result<(), Box<dyn std::error::Error>>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let file: DietaryRulesFile = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        for (phrase, groups) in file.contains {
+            self.insert(&phrase, &groups);
+        }
+        for (phrase, groups) in file.free_of {
+            self.insert_free_of(&phrase, &groups);
+        }
+        Ok(())
+    }
+    /// The groups an ingredient name is in, with the phrase that put it there. A phrase inside a
+    /// longer one doesn't count, ex: "butter" in "peanut butter"
+    pub fn classify(&self, ingredient: &str) -> Vec<(FoodGroup, String)> {
+        let name = normalize_ingredient_name(ingredient);
+        let words = name.split(' ').collect::<Vec<_>>();
+        let found = phrase_spans(&words, &self.contains);
+        let removed = phrase_spans(&words, &self.free_of)
+            .into_iter()
+            .flat_map(|(_, _, rule)| rule.groups.iter().copied())
+            .collect::<Vec<_>>();
+        let mut groups = found
+            .iter()
+            .filter(|(start, end, _)| {
+                !found
+                    .iter()
+                    .any(|(s, e, _)| s <= start && e >= end && (e - s) > (end - start))
+            })
+            .flat_map(|(_, _, rule)| rule.groups.iter().map(|g| (*g, rule.phrase.clone())))
+            .filter(|(g, _)| !removed.contains(g))
+            .collect::<Vec<_>>();
+        groups.sort();
+        groups.dedup_by_key(|(g, _)| *g);
+        groups
+    }
+}
+
+impl DietaryRule {
+    pub fn new(phrase: &str, groups: &[FoodGroup]) -> Self {
+        DietaryRule {
+            phrase: phrase.into(),
+            groups: groups.to_vec(),
+        }
+    }
+}
+
+// Where each phrase of `table` appears in `words`, as word spans
+fn phrase_spans<'a>(words: &[&str], table: &'a HashMap<String, DietaryRule>) -> Vec<(usize, usize, &'a DietaryRule)> {
+    let mut spans = Vec::new();
+    for (phrase, rule) in table.iter() {
+        let len = phrase.split(' ').count();
+        for i in 0..(words.len() + 1).saturating_sub(len) {
+            if words[i..i + len].join(" ") == *phrase {
+                spans.push((i, i + len, rule));
+            }
+        }
+    }
+    spans
+}
+
+/// An ingredient that put the recipe in a food group
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DietaryMatch {
+    pub group: FoodGroup,
+    /// Index into the recipe's ingredients
+    pub ingredient: usize,
+    /// The rule phrase that matched, ex: "parmesan"
+    pub phrase: String,
+}
+
+/// Tags worked out from the ingredients, only as good as the rules, an ingredient no rule knows
+/// about counts as safe
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DietaryProfile {
+    pub tags: Vec<DietaryTag>,
+    pub matches: Vec<DietaryMatch>,
+}
+
+impl DietaryProfile {
+    pub fn analyze(ingredients: &[RecipeItem], rules: &DietaryRules) -> Self {
+        let matches = ingredients
+            .iter()
+            .enumerate()
+            .flat_map(|(i, item)| {
+                rules
+                    .classify(&item.name)
+                    .into_iter()
+                    .map(move |(group, phrase)| DietaryMatch { group, ingredient: i, phrase })
+            })
+            .collect::<Vec<_>>();
+        let groups = matches.iter().map(|m| m.group).collect::<Vec<_>>();
+        DietaryProfile {
+            tags: DietaryTag::from_groups(&groups),
+            matches,
+        }
+    }
+    pub fn has(&self, tag: DietaryTag) -> bool {
+        self.tags.contains(&tag)
+    }
+    /// Whether every one of `tags` applies, ex: a guest who needs vegetarian and nut-free
+    pub fn satisfies(&self, tags: &[DietaryTag]) -> bool {
+        tags.iter().all(|t| self.has(*t))
+    }
+    /// The ingredients that put the recipe in `group`
+    pub fn explain(&self, group: FoodGroup) -> impl Iterator<Item = &DietaryMatch> {
+        self.matches.iter().filter(move |m| m.group == group)
+    }
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+}
+
+impl ParsedRecipe {
+    /// Work out the dietary tags from the ingredients and store them on the recipe
+    pub fn analyze_dietary(&mut self, rules: &DietaryRules) {
+        self.dietary = DietaryProfile::analyze(&self.data.ingredients, rules);
+    }
+    /// Why the recipe isn't `tag`, ex: "contains dairy: heavy cream (cream)"
+    pub fn explain_dietary(&self, tag: DietaryTag) -> Vec<String> {
+        let groups = match tag {
+            DietaryTag::Contains(group) => vec![group],
+            tag => tag.excluded_groups(),
+        };
+        groups
+            .iter()
+            .flat_map(|g| self.dietary.explain(*g))
+            .filter_map(|m| {
+                let item = self.data.ingredients.get(m.ingredient)?;
+                Some(format!("contains {}: {} ({})", m.group.name(), item.name.trim(), m.phrase))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{
+    formatting::pluralize, timers::is_number, DirectionSection, DirectionStep, Measure, MeasureType, MixedRational,
+    QuantityFormat, Unit,
+};
+
+/// Text between these is never scaled, ex: "grease a {9x13 inch} pan", they're hidden when displayed
+pub const NO_SCALE_OPEN: char = '{';
+pub const NO_SCALE_CLOSE: char = '}';
+
+const TRAILING_PUNCTUATION: &[char] = &[',', '.', ';', ':', ')', '!'];
+
+// A measurement unit written in a step, up to two words long, ex: "fl oz"
+fn read_unit(tokens: &[(usize, &str)], j: usize) -> Option<(Measure, usize)> {
+    if j >= tokens.len() {
+        return None;
+    }
+    [2, 1].iter().find_map(|len| {
+        let words = tokens.get(j..j + len)?;
+        // Punctuation ends the unit, "2 cups, tablespoons" isn't one unit
+        if words[..len - 1].iter().any(|(_, w)| w.ends_with(TRAILING_PUNCTUATION)) {
+            return None;
+        }
+        let name = words
+            .iter()
+            .map(|(_, w)| w.trim_end_matches(TRAILING_PUNCTUATION).to_lowercase())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let measure = Measure::new(name);
+        (measure.unit != Unit::Other && !measure.unit.is_temperature()).then_some((measure, j + len - 1))
+    })
+}
+
+// Keep the word the recipe used when the unit didn't change, just fix its plural
+fn unit_word(original: &str, measure: &Measure, plural: bool) -> String {
+    if !original.to_lowercase().starts_with(measure.long_name()) {
+        original.into()
+    } else if plural {
+        pluralize(original)
+    } else if let Some(singular) = ["ches", "shes", "xes"]
+        .iter()
+        .find(|e| original.ends_with(*e))
+        .map(|_| &original[..original.len() - 2])
+    {
+        singular.into()
+    } else {
+        original.strip_suffix('s').unwrap_or(original).into()
+    }
+}
+
+/// Multiply every quantity with a unit in `text`, ex: "add 2 tablespoons of the butter" doubled
+/// is "add 4 tablespoons of the butter", bare numbers like times and pan sizes are left alone
+pub fn scale_quantities(text: &str, rhs: MixedRational) -> String {
+    if rhs == MixedRational::whole(1) {
+        return text.into();
+    }
+    // Everything between the markers is skipped
+    let mut skipped = Vec::new();
+    let mut open = None;
+    for (i, c) in text.char_indices() {
+        match c {
+            NO_SCALE_OPEN => open = open.or(Some(i)),
+            NO_SCALE_CLOSE => skipped.extend(open.take().map(|o| o..i)),
+            _ => {}
+        }
+    }
+    skipped.extend(open.map(|o| o..text.len()));
+    let tokens = text
+        .split_whitespace()
+        .map(|t| (t.as_ptr() as usize - text.as_ptr() as usize, t))
+        .filter(|(start, _)| !skipped.iter().any(|r| r.contains(start)))
+        .collect::<Vec<_>>();
+    let fmt = QuantityFormat::default();
+    let mut replacements = Vec::new();
+    let mut k = 0;
+    while k < tokens.len() {
+        // Numbers and ranges, ex: 1 1/2, 2-3, 2 to 3, a size in parenthesis ends the run
+        let mut j = k;
+        while let Some((_, t)) = tokens.get(j) {
+            let joins = j > k && ["to", "or", "-", "–"].contains(&t.to_lowercase().as_str());
+            if is_number(t) || (joins && tokens.get(j + 1).is_some_and(|(_, n)| is_number(n))) {
+                j += 1;
+            } else {
+                break;
+            }
+        }
+        if j == k {
+            k += 1;
+            continue;
+        }
+        // A size between the count and its container, ex: 1 (14 oz) can
+        let size_end = if tokens.get(j).is_some_and(|(_, t)| t.starts_with('(')) {
+            tokens[j..].iter().position(|(_, t)| t.ends_with(')')).map_or(j, |p| j + p + 1)
+        } else {
+            j
+        };
+        let Some((measure, unit_end)) = read_unit(&tokens, size_end) else {
+            k = j;
+            continue;
+        };
+        let (start, (last_start, last)) = (tokens[k].0, tokens[j - 1]);
+        let Ok((count, count_b)) = MixedRational::try_from_string(&text[start..last_start + last.len()]) else {
+            k = unit_end + 1;
+            continue;
+        };
+        let (unit_last_start, unit_last) = tokens[unit_end];
+        let end = unit_last_start + unit_last.trim_end_matches(TRAILING_PUNCTUATION).len();
+        let scaled = MeasureType { count, unit: measure } * rhs;
+        let scaled_b = count_b.map(|b| {
+            let b = MeasureType { count: b * rhs, unit: measure };
+            b.unit.convert(b.count, scaled.unit).unwrap_or(b.count)
+        });
+        let upper = scaled_b.unwrap_or(scaled.count);
+        let plural = upper > MixedRational::whole(1);
+        let unit_start = tokens[size_end].0;
+        let unit = if scaled.unit.unit == measure.unit {
+            unit_word(&text[unit_start..end], &measure, plural)
+        } else {
+            fmt.unit(&scaled.unit, plural)
+        };
+        let quantity = match scaled_b {
+            Some(b) => format!("{}-{}", fmt.quantity(scaled.count), fmt.quantity(b)),
+            None => fmt.quantity(scaled.count),
+        };
+        replacements.push((unit_start..end, unit));
+        replacements.push((start..last_start + last.len(), quantity));
+        k = unit_end + 1;
+    }
+    let mut scaled = text.to_string();
+    replacements.sort_by_key(|(range, _)| range.start);
+    for (range, replacement) in replacements.into_iter().rev() {
+        scaled.replace_range(range, &replacement);
+    }
+    scaled
+}
+
+impl DirectionStep {
+    /// The text with the no scale markers taken out
+    pub fn display_text(&self) -> String {
+        self.text.replace([NO_SCALE_OPEN, NO_SCALE_CLOSE], "")
+    }
+}
+
+impl std::ops::Mul<MixedRational> for DirectionStep {
+    type Output = DirectionStep;
+    fn mul(self, rhs: MixedRational) -> Self::Output {
+        DirectionStep {
+            text: scale_quantities(&self.text, rhs),
+            ..self
+        }
+    }
+}
+
+impl std::ops::Mul<MixedRational> for DirectionSection {
+    type Output = DirectionSection;
+    fn mul(self, rhs: MixedRational) -> Self::Output {
+        DirectionSection {
+            name: self.name,
+            sections: self.sections.into_iter().map(|s| s * rhs).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_ending_in_a_number() {
+        let double = MixedRational::whole(2);
+        assert_eq!(scale_quantities("Bake at 350", double), "Bake at 350");
+        assert_eq!(
+            scale_quantities("Add 2 tablespoons butter. Makes 12", double),
+            "Add 4 tablespoons butter. Makes 12"
+        );
+        assert_eq!(scale_quantities("1 1/2", double), "1 1/2");
+    }
+
+    #[test]
+    fn scales_units_and_leaves_bare_numbers() {
+        let half = MixedRational::fract(1, 2);
+        assert_eq!(
+            scale_quantities("Whisk 2 cups milk for 5 minutes", half),
+            "Whisk 1 cup milk for 5 minutes"
+        );
+        assert_eq!(scale_quantities("Grease a {9 inch} pan", half), "Grease a {9 inch} pan");
+    }
+
+    #[test]
+    fn scaled_step_keeps_its_text_otherwise() {
+        let step = DirectionStep::new("Simmer 2-3 cups of stock, then rest 10".into());
+        let scaled = step * MixedRational::whole(2);
+        assert_eq!(scaled.text, "Simmer 4-6 cups of stock, then rest 10");
+    }
+}
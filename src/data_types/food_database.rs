@@ -0,0 +1,443 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+};
+
+use crate::{
+    core_words, normalize_ingredient_name, DensityTable, Measure, MeasureType, MixedRational, Nutrient,
+    NutrientAmount, NutrientUnit, NutritionBasis, NutritionInfo, RecipeData, RecipeItem, Unit, MAX_DENOMINATOR,
+};
+
+// FoodData Central nutrient ids, mono and polyunsaturated fat add up to unsaturated fat
+#[rustfmt::skip]
+const FDC_NUTRIENTS: &[(u64, Nutrient, NutrientUnit)] = &[
+    (1008, Nutrient::Energy, NutrientUnit::Kilocalorie), (1004, Nutrient::Fat, NutrientUnit::Gram),
+    (1258, Nutrient::SaturatedFat, NutrientUnit::Gram), (1257, Nutrient::TransFat, NutrientUnit::Gram),
+    (1292, Nutrient::UnsaturatedFat, NutrientUnit::Gram), (1293, Nutrient::UnsaturatedFat, NutrientUnit::Gram),
+    (1253, Nutrient::Cholesterol, NutrientUnit::Milligram), (1093, Nutrient::Sodium, NutrientUnit::Milligram),
+    (1005, Nutrient::Carbohydrate, NutrientUnit::Gram), (1079, Nutrient::Fiber, NutrientUnit::Gram),
+    (2000, Nutrient::Sugar, NutrientUnit::Gram), (1235, Nutrient::AddedSugar, NutrientUnit::Gram),
+    (1003, Nutrient::Protein, NutrientUnit::Gram), (1114, Nutrient::VitaminD, NutrientUnit::Microgram),
+    (1087, Nutrient::Calcium, NutrientUnit::Milligram), (1089, Nutrient::Iron, NutrientUnit::Milligram),
+    (1092, Nutrient::Potassium, NutrientUnit::Milligram),
+];
+
+/// A household measure of a food and what it weighs, ex: 1 large egg is 50 g
+#[derive(Clone, Debug)]
+pub struct Portion {
+    pub amount: MixedRational,
+    pub unit: Measure,
+    pub description: String,
+    pub grams: MixedRational,
+}
+
+#[derive(Clone, Debug)]
+pub struct Food {
+    pub id: u64,
+    pub description: String,
+    /// Per 100 g
+    pub nutrients: Vec<NutrientAmount>,
+    pub portions: Vec<Portion>,
+}
+
+impl Food {
+    /// What `measure` of this food weighs, None if it isn't a weight or one of its portions
+    pub fn grams(&self, measure: MeasureType, density: Option<MixedRational>) -> Option<MixedRational> {
+        if let Some(grams) = measure.convert_with_density(Unit::Gram, density) {
+            return Some(grams.count);
+        }
+        // Portions in the same unit first, then a plain count like "2 eggs" by the first whole one
+        let portion = self
+            .portions
+            .iter()
+            .find(|p| measure.unit.convert(measure.count, p.unit).is_some() && p.unit.unit != Unit::Other)
+            .or_else(|| {
+                (measure.unit.unit == Unit::Other)
+                    .then(|| self.portions.iter().find(|p| p.unit.unit == Unit::Other))
+                    .flatten()
+            })?;
+        if portion.amount.is_zero() {
+            return None;
+        }
+        let count = measure.unit.convert(measure.count, portion.unit).unwrap_or(measure.count);
+        Some(count / portion.amount * portion.grams)
+    }
+}
+
+/// Foods with their nutrients, loaded from a FoodData Central CSV export
+#[derive(Clone, Debug, Default)]
+pub struct FoodDatabase {
+    pub foods: Vec<Food>,
+    /// Ingredient names mapped straight to a food id, checked before searching
+    pub aliases: HashMap<String, u64>,
+    words: HashMap<String, Vec<usize>>,
+}
+
+impl FoodDatabase {
+    /// Load `food.csv` and `food_nutrient.csv` from an unzipped FoodData Central download,
+    /// `food_portion.csv` and `measure_unit.csv` are used when they're there
+    pub fn load_fdc<P>(dir: P) -> Result<Self, Box<dyn Error>>
+    where
+        P: AsRef<Path>,
+    {
+        let dir = dir.as_ref();
+        let mut db = FoodDatabase::default();
+        let mut index = HashMap::new();
+        // Read a row at a time, food_nutrient.csv alone is gigabytes
+        let foods = CsvReader::open(dir.join("food.csv"))?;
+        let (id, description) = (foods.column("fdc_id")?, foods.column("description")?);
+        for row in foods {
+            let mut row = row?;
+            let Ok(fdc_id) = row[id].parse::<u64>() else {
+                continue;
+            };
+            index.insert(fdc_id, db.foods.len());
+            db.foods.push(Food {
+                id: fdc_id,
+                description: std::mem::take(&mut row[description]),
+                nutrients: Vec::new(),
+                portions: Vec::new(),
+            });
+        }
+
+        let nutrients = CsvReader::open(dir.join("food_nutrient.csv"))?;
+        let (id, nutrient, amount) = (
+            nutrients.column("fdc_id")?,
+            nutrients.column("nutrient_id")?,
+            nutrients.column("amount")?,
+        );
+        for row in nutrients {
+            let row = row?;
+            let known = row[nutrient]
+                .parse::<u64>()
+                .ok()
+                .and_then(|n| FDC_NUTRIENTS.iter().find(|(i, _, _)| *i == n));
+            let Some((_, nutrient, unit)) = known else {
+                continue;
+            };
+            let food = row[id].parse::<u64>().ok().and_then(|i| index.get(&i));
+            let (Some(food), Ok(amount)) = (food, row[amount].parse::<MixedRational>()) else {
+                continue;
+            };
+            add_nutrient(&mut db.foods[*food].nutrients, NutrientAmount::new(*nutrient, amount, *unit));
+        }
+
+        let mut units = HashMap::new();
+        if let Ok(table) = CsvReader::open(dir.join("measure_unit.csv")) {
+            let (id, name) = (table.column("id")?, table.column("name")?);
+            for row in table {
+                let mut row = row?;
+                units.insert(std::mem::take(&mut row[id]), std::mem::take(&mut row[name]));
+            }
+        }
+        if let Ok(portions) = CsvReader::open(dir.join("food_portion.csv")) {
+            let id = portions.column("fdc_id")?;
+            let grams = portions.column("gram_weight")?;
+            let columns = ["amount", "measure_unit_id", "portion_description", "modifier"].map(|c| portions.column(c).ok());
+            for row in portions {
+                let row = row?;
+                let food = row[id].parse::<u64>().ok().and_then(|i| index.get(&i));
+                let (Some(food), Ok(grams)) = (food, row[grams].parse::<MixedRational>()) else {
+                    continue;
+                };
+                let get = |c: Option<usize>| c.map_or("", |c| row[c].as_str());
+                // SR Legacy keeps the unit in the modifier, ex: "cup, chopped", "large"
+                let description = [get(columns[2]), get(columns[3])].join(" ").trim().to_string();
+                let unit_name = units
+                    .get(get(columns[1]))
+                    .filter(|u| u.as_str() != "undetermined")
+                    .map_or(get(columns[3]), |u| u.as_str());
+                let unit = Measure::new(unit_name.split([',', ' ', '(']).next().unwrap_or_default().to_string());
+                db.foods[*food].portions.push(Portion {
+                    amount: get(columns[0]).parse().unwrap_or(MixedRational::whole(1)),
+                    unit,
+                    description,
+                    grams,
+                });
+            }
+        }
+        db.index_words();
+        Ok(db)
+    }
+    pub fn insert(&mut self, food: Food) {
+        self.foods.push(food);
+        self.index_food(self.foods.len() - 1);
+    }
+    pub fn alias(&mut self, ingredient: &str, food_id: u64) {
+        self.aliases.insert(normalize_ingredient_name(ingredient), food_id);
+    }
+    pub fn get(&self, id: u64) -> Option<&Food> {
+        self.foods.iter().find(|f| f.id == id)
+    }
+    fn index_words(&mut self) {
+        self.words.clear();
+        for i in 0..self.foods.len() {
+            self.index_food(i);
+        }
+    }
+    fn index_food(&mut self, i: usize) {
+        for word in normalize_ingredient_name(&self.foods[i].description).split(' ') {
+            let foods = self.words.entry(word.to_string()).or_default();
+            if foods.last() != Some(&i) {
+                foods.push(i);
+            }
+        }
+    }
+    /// The food that best fits an ingredient name, the one describing it with the fewest other words
+    pub fn lookup(&self, ingredient: &str) -> Option<&Food> {
+        if let Some(food) = self.aliases.get(&normalize_ingredient_name(ingredient)) {
+            return self.get(*food);
+        }
+        let core = core_words(ingredient);
+        let rarest = core.iter().filter_map(|w| self.words.get(w)).min_by_key(|f| f.len())?;
+        rarest
+            .iter()
+            .map(|i| &self.foods[*i])
+            .filter_map(|food| {
+                let name = normalize_ingredient_name(&food.description);
+                let words = name.split(' ').collect::<Vec<_>>();
+                if !core.iter().all(|c| words.contains(&c.as_str())) {
+                    return None;
+                }
+                // The food is named first, salt isn't "Peanut butter, with salt" and butter is
+                // "Butter, salted" over "Peanut butter, smooth"
+                let head = normalize_ingredient_name(food.description.split(',').next().unwrap_or_default());
+                if !head.split(' ').any(|w| core.iter().any(|c| c == w)) {
+                    return None;
+                }
+                let head_matches = head.split(' ').all(|w| core.iter().any(|c| c == w));
+                Some(((!head_matches, words.len() - core.len(), food.description.len()), food))
+            })
+            .min_by_key(|(score, _)| *score)
+            .map(|(_, food)| food)
+    }
+    /// Nutrition per serving of `recipe` added up from its ingredients
+    pub fn estimate(&self, recipe: &RecipeData, densities: &DensityTable) -> NutritionEstimate {
+        let mut estimate = NutritionEstimate::default();
+        let mut totals = Vec::new();
+        for (i, item) in recipe.ingredients.iter().enumerate() {
+            let Some(food) = self.lookup(&item.name) else {
+                estimate.unmatched.push(UnmatchedIngredient { ingredient: i, reason: UnmatchedReason::NoFood });
+                continue;
+            };
+            let Some(grams) = item_grams(item, food, densities) else {
+                estimate.unmatched.push(UnmatchedIngredient { ingredient: i, reason: UnmatchedReason::NoWeight });
+                continue;
+            };
+            for nutrient in food.nutrients.iter() {
+                let amount = (nutrient.amount * grams / MixedRational::whole(100)).limit_denominator(MAX_DENOMINATOR);
+                add_nutrient(&mut totals, NutrientAmount { amount, ..*nutrient });
+            }
+            estimate.matched.push(MatchedIngredient { ingredient: i, food: food.id, grams });
+        }
+        let servings = if recipe.servings.is_zero() { MixedRational::whole(1) } else { recipe.servings };
+        totals.sort_by_key(|n| Nutrient::ALL.iter().position(|a| *a == n.nutrient));
+        for nutrient in totals.iter_mut() {
+            nutrient.amount = (nutrient.amount / servings).limit_denominator(MAX_DENOMINATOR);
+        }
+        estimate.nutrition = NutritionInfo {
+            nutrients: totals,
+            basis: NutritionBasis::PerServing,
+            ..Default::default()
+        };
+        estimate
+    }
+}
+
+// Ranges are counted by their lower end
+fn item_grams(item: &RecipeItem, food: &Food, densities: &DensityTable) -> Option<MixedRational> {
+    let measure = item.contents().unwrap_or(item.measure);
+    let grams = food.grams(measure, densities.lookup(&item.name));
+    grams.map(|g| g.limit_denominator(MAX_DENOMINATOR))
+}
+
+fn add_nutrient(nutrients: &mut Vec<NutrientAmount>, amount: NutrientAmount) {
+    match nutrients.iter_mut().find(|n| n.nutrient == amount.nutrient) {
+        Some(existing) => {
+            if let Some(converted) = amount.convert(existing.unit) {
+                existing.amount += converted.amount;
+            }
+        }
+        None => nutrients.push(amount),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnmatchedReason {
+    /// Nothing in the database has that name
+    NoFood,
+    /// The food was found but the amount couldn't be turned into grams
+    NoWeight,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnmatchedIngredient {
+    /// Index into the recipe's ingredients
+    pub ingredient: usize,
+    pub reason: UnmatchedReason,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MatchedIngredient {
+    pub ingredient: usize,
+    pub food: u64,
+    pub grams: MixedRational,
+}
+
+/// Nutrition worked out from ingredients, leaves out anything in `unmatched`
+#[derive(Clone, Debug, Default)]
+pub struct NutritionEstimate {
+    pub nutrition: NutritionInfo,
+    pub matched: Vec<MatchedIngredient>,
+    pub unmatched: Vec<UnmatchedIngredient>,
+}
+
+impl RecipeData {
+    pub fn estimate_nutrition(&self, db: &FoodDatabase) -> NutritionEstimate {
+        db.estimate(self, DensityTable::shared())
+    }
+    /// Estimate nutrition when the recipe doesn't come with any
+    pub fn fill_nutrition(&mut self, db: &FoodDatabase) -> Option<NutritionEstimate> {
+        if !self.nutrition_info.nutrients.is_empty() {
+            return None;
+        }
+        let estimate = self.estimate_nutrition(db);
+        self.nutrition_info = estimate.nutrition.clone();
+        Some(estimate)
+    }
+}
+
+// Just enough CSV for FoodData Central, quoted fields can hold commas, quotes and newlines. Rows
+// are read one at a time, ones with a different number of fields than the header are skipped
+struct CsvReader {
+    reader: BufReader<File>,
+    header: Vec<String>,
+}
+
+impl CsvReader {
+    fn open<P>(path: P) -> Result<Self, Box<dyn Error>>
+    where
+        P: AsRef<Path>,
+    {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut header = read_record(&mut reader)?.ok_or("empty csv file")?;
+        if let Some(first) = header.first_mut() {
+            *first = first.trim_start_matches('\u{feff}').to_string();
+        }
+        Ok(CsvReader { reader, header })
+    }
+    fn column(&self, name: &str) -> Result<usize, Box<dyn Error>> {
+        self.header
+            .iter()
+            .position(|h| h == name)
+            .ok_or_else(|| format!("missing column \"{}\"", name).into())
+    }
+}
+
+impl Iterator for CsvReader {
+    type Item = io::Result<Vec<String>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match read_record(&mut self.reader) {
+                Ok(Some(record)) if record.len() != self.header.len() => continue,
+                record => return record.transpose(),
+            }
+        }
+    }
+}
+
+// The next record, its lines are joined until every quote is closed
+fn read_record<R: BufRead>(reader: &mut R) -> io::Result<Option<Vec<String>>> {
+    let mut text = String::new();
+    loop {
+        if reader.read_line(&mut text)? == 0 || text.matches('"').count().is_multiple_of(2) {
+            break;
+        }
+    }
+    if text.is_empty() {
+        return Ok(None);
+    }
+    let text = text.strip_suffix('\n').unwrap_or(&text);
+    let text = text.strip_suffix('\r').unwrap_or(text);
+    let (mut record, mut field) = (Vec::new(), String::new());
+    let mut chars = text.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') => quoted = true,
+            (false, ',') => record.push(std::mem::take(&mut field)),
+            (false, c) => field.push(c),
+        }
+    }
+    record.push(field);
+    Ok(Some(record))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> FoodDatabase {
+        FoodDatabase::load_fdc(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/fdc")).unwrap()
+    }
+
+    #[test]
+    fn loads_rows_and_known_nutrients() {
+        let db = fixture();
+        assert_eq!(db.foods.len(), 5);
+        assert_eq!(db.get(999999).unwrap().description, "A \"quoted\" food\nacross lines");
+        let egg = db.get(171287).unwrap();
+        // Water (1051) isn't one of ours
+        assert_eq!(egg.nutrients.len(), 3);
+        assert_eq!(egg.portions[0].grams, MixedRational::whole(50));
+    }
+
+    #[test]
+    fn lookup_by_head_word() {
+        let db = fixture();
+        assert_eq!(db.lookup("eggs").map(|f| f.id), Some(171287));
+        assert_eq!(db.lookup("all-purpose flour").map(|f| f.id), Some(169761));
+        assert_eq!(db.lookup("butter").map(|f| f.id), Some(173410));
+        assert_eq!(db.lookup("peanut butter").map(|f| f.id), Some(172470));
+        assert!(db.lookup("saffron").is_none());
+    }
+
+    #[test]
+    fn grams_from_weights_and_portions() {
+        let db = fixture();
+        let measure = |line: &str| RecipeItem::parse(line, None).unwrap().measure;
+        let (egg, flour) = (db.get(171287).unwrap(), db.get(169761).unwrap());
+        assert_eq!(egg.grams(measure("2 eggs"), None), Some(MixedRational::whole(100)));
+        assert_eq!(flour.grams(measure("1/2 cup flour"), None), Some(MixedRational::fract(125, 2)));
+        assert_eq!(flour.grams(measure("1 kg flour"), None), Some(MixedRational::whole(1000)));
+        assert_eq!(egg.grams(measure("1 tbsp egg"), None), None);
+    }
+
+    #[test]
+    fn estimate_adds_up_ingredients() {
+        let db = fixture();
+        let recipe = RecipeData {
+            ingredients: ["2 eggs", "1 cup flour", "1 pinch saffron"]
+                .iter()
+                .filter_map(|l| RecipeItem::parse(l, None))
+                .collect(),
+            servings: MixedRational::whole(2),
+            ..Default::default()
+        };
+        let estimate = db.estimate(&recipe, &DensityTable::default());
+        assert_eq!(estimate.matched.len(), 2);
+        assert_eq!(estimate.unmatched, vec![UnmatchedIngredient { ingredient: 2, reason: UnmatchedReason::NoFood }]);
+        let energy = estimate.nutrition.nutrients.iter().find(|n| n.nutrient == Nutrient::Energy).unwrap();
+        // (143 + 455) / 2 servings
+        assert_eq!(energy.amount, MixedRational::whole(299));
+    }
+}
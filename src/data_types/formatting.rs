@@ -0,0 +1,471 @@
+use crate::{format_duration, mixed_rational::VULGAR_FRACTIONS, IngredientFlag, NutritionBasis, Measure, MixedRational, ParsedRecipe, RecipeItem, Unit};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FractionStyle {
+    /// 1½
+    Unicode,
+    /// 1 1/2
+    Ascii,
+    /// 1.5, rounded to at most n digits
+    Decimal(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnitNames {
+    /// tbsp, c, ml
+    Short,
+    /// tablespoon, cup, milliliter
+    Long,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct QuantityFormat {
+    pub style: FractionStyle,
+    pub unit_names: UnitNames,
+    /// Pluralize units and names from the quantity, otherwise keep what the source used
+    pub pluralize: bool,
+    pub decimal_separator: char,
+}
+
+impl Default for QuantityFormat {
+    fn default() -> Self {
+        QuantityFormat {
+            style: FractionStyle::Ascii,
+            unit_names: UnitNames::Long,
+            pluralize: true,
+            decimal_separator: '.',
+        }
+    }
+}
+
+// Words that read the same no matter the quantity
+const UNCOUNTABLE: &[&str] = &[
+    "butter", "flour", "garlic", "milk", "oil", "pepper", "rice", "salt", "sugar", "water",
+    "cream", "cheese", "juice", "broth", "stock", "wine", "vinegar", "honey", "parsley",
+];
+
+impl QuantityFormat {
+    pub fn unicode() -> Self {
+        QuantityFormat {
+            style: FractionStyle::Unicode,
+            ..Default::default()
+        }
+    }
+    pub fn decimal(digits: usize) -> Self {
+        QuantityFormat {
+            style: FractionStyle::Decimal(digits),
+            ..Default::default()
+        }
+    }
+
+    pub fn quantity(&self, q: MixedRational) -> String {
+        if q.is_zero() {
+            return "".into();
+        }
+        let sign = if q.is_negative() { "-" } else { "" };
+        let q = q.abs();
+        let (whole, num, den) = (q.value, q.num, q.den);
+        match self.style {
+            FractionStyle::Ascii => format!("{}{}", sign, q),
+            FractionStyle::Unicode => {
+                let glyph = VULGAR_FRACTIONS
+                    .iter()
+                    .find(|(_, n, d)| *n == num && *d == den)
+                    .map(|(c, _, _)| *c);
+                match (glyph, whole) {
+                    (_, _) if den == 0 => format!("{}{}", sign, whole),
+                    (Some(c), 0) => format!("{}{}", sign, c),
+                    (Some(c), w) => format!("{}{}{}", sign, w, c),
+                    (None, _) => format!("{}{}", sign, q),
+                }
+            }
+            FractionStyle::Decimal(digits) => {
+                let s = format!("{:.*}", digits, q.to_float());
+                let s = if s.contains('.') {
+                    s.trim_end_matches('0').trim_end_matches('.').to_string()
+                } else {
+                    s
+                };
+                format!("{}{}", sign, s.replace('.', &self.decimal_separator.to_string()))
+            }
+        }
+    }
+
+    pub fn unit(&self, measure: &Measure, plural: bool) -> String {
+        if measure.unit as u8 == Unit::Other as u8 {
+            return measure.names[0].to_string();
+        }
+        match self.unit_names {
+            // Counted units have no abbreviation, so they always read as words
+            _ if measure.unit.is_temperature() => measure.long_name().to_string(),
+            _ if measure.unit.is_count() && plural => pluralize(measure.long_name()),
+            UnitNames::Short => measure.short_name().to_string(),
+            UnitNames::Long if plural => pluralize(measure.long_name()),
+            UnitNames::Long => measure.long_name().to_string(),
+        }
+    }
+
+    // Metric amounts read as decimals whatever the style, ex: 1.5 l rather than 1 1/2 l
+    fn for_unit(&self, measure: &Measure) -> QuantityFormat {
+        match self.style {
+            FractionStyle::Ascii | FractionStyle::Unicode if measure.unit.is_decimal() => QuantityFormat {
+                style: FractionStyle::Decimal(2),
+                ..*self
+            },
+            _ => *self,
+        }
+    }
+
+    pub fn item(&self, item: &RecipeItem) -> String {
+        let fmt = self.for_unit(&item.measure.unit);
+        let upper = item.measure_b.map(|m| m.count).unwrap_or(item.measure.count);
+        let plural = if self.pluralize {
+            upper > MixedRational::whole(1)
+        } else {
+            item.plural
+        };
+        let count = match item.measure_b {
+            Some(b) => format!(
+                "{}-{}",
+                fmt.quantity(item.measure.count),
+                fmt.quantity(b.count)
+            ),
+            None => fmt.quantity(item.measure.count),
+        };
+        let is_counted = item.measure.unit.unit as u8 == Unit::Other as u8;
+        let name = if self.pluralize && is_counted && plural {
+            pluralize_phrase(item.name.trim())
+        } else if self.pluralize && is_counted && !upper.is_zero() {
+            singularize_phrase(item.name.trim())
+        } else {
+            item.name.trim().to_string()
+        };
+        // ex: 2 (14.5 oz) cans
+        let size = item
+            .container_size
+            .map(|s| format!("({} {})", self.for_unit(&s.unit).quantity(s.count), self.unit(&s.unit, s.count > MixedRational::whole(1))))
+            .unwrap_or_default();
+        let mut parts = vec![count, size, self.unit(&item.measure.unit, plural), name];
+        parts.retain(|p| !p.trim().is_empty());
+        let mut s = parts.join(" ");
+        for alternative in item.alternatives.iter() {
+            s.push_str(" or ");
+            if let Some(m) = alternative.measure {
+                s.push_str(&self.quantity(m.count));
+                s.push(' ');
+                let unit = self.unit(&m.unit, m.count > MixedRational::whole(1));
+                if !unit.is_empty() {
+                    s.push_str(&unit);
+                    s.push(' ');
+                }
+            }
+            s.push_str(alternative.name.trim());
+        }
+        if let Some(n) = &item.note {
+            s.push_str(&format!(" ({})", n));
+        }
+        for flag in item.flags.iter().filter(|f| **f != IngredientFlag::Optional) {
+            s.push_str(&format!(", {}", flag.phrase()));
+        }
+        if item.has_flag(IngredientFlag::Optional) {
+            s.push_str(" (optional)");
+        }
+        s
+    }
+}
+
+/// Plural of a single english word, ex: pinch -> pinches, berry -> berries
+pub fn pluralize(word: &str) -> String {
+    let lower = word.to_lowercase();
+    if word.is_empty() || lower.ends_with('s') || UNCOUNTABLE.contains(&lower.as_str()) {
+        return word.into();
+    }
+    let consonant_y = lower.ends_with('y')
+        && !lower[..lower.len() - 1].ends_with(['a', 'e', 'i', 'o', 'u']);
+    if consonant_y {
+        format!("{}ies", &word[..word.len() - 1])
+    } else if lower.ends_with("leaf") || lower.ends_with("loaf") || lower.ends_with("half") {
+        format!("{}ves", &word[..word.len() - 1])
+    } else if ["ch", "sh", "x", "z", "to"].iter().any(|e| lower.ends_with(e)) {
+        format!("{}es", word)
+    } else {
+        format!("{}s", word)
+    }
+}
+
+/// Pluralize the head noun of an ingredient, ex: "large egg, beaten" -> "large eggs, beaten"
+pub fn pluralize_phrase(name: &str) -> String {
+    let (head, rest) = match name.find(',') {
+        Some(i) => name.split_at(i),
+        None => (name, ""),
+    };
+    let head = head.trim_end();
+    match head.rfind(' ') {
+        Some(i) => format!("{} {}{}", &head[..i], pluralize(&head[i + 1..]), rest),
+        None => format!("{}{}", pluralize(head), rest),
+    }
+}
+
+/// The singular of a plural noun, words that aren't plurals are left as is, ex: "tomatoes" -> "tomato"
+pub fn singularize(word: &str) -> String {
+    let lower = word.to_lowercase();
+    if UNCOUNTABLE.contains(&lower.as_str()) || ["ss", "us", "is"].iter().any(|e| lower.ends_with(e)) {
+        return word.into();
+    }
+    let stem = |n: usize| word[..word.len() - n].to_string();
+    if lower.ends_with("ies") && lower.len() > 4 {
+        format!("{}y", stem(3))
+    } else if ["leaves", "loaves", "halves"].iter().any(|e| lower.ends_with(e)) {
+        format!("{}f", stem(3))
+    } else if ["ches", "shes", "xes", "zes", "toes"].iter().any(|e| lower.ends_with(e)) {
+        stem(2)
+    } else if lower.ends_with('s') {
+        stem(1)
+    } else {
+        word.into()
+    }
+}
+
+/// Singularize the last word before a comma, ex: "eggs, beaten" -> "egg, beaten"
+pub fn singularize_phrase(name: &str) -> String {
+    let (head, rest) = match name.find(',') {
+        Some(i) => name.split_at(i),
+        None => (name, ""),
+    };
+    let head = head.trim_end();
+    match head.rfind(' ') {
+        Some(i) => format!("{} {}{}", &head[..i], singularize(&head[i + 1..]), rest),
+        None => format!("{}{}", singularize(head), rest),
+    }
+}
+
+impl Measure {
+    pub fn short_name(&self) -> &'static str {
+        match self.unit {
+            Unit::Milliliter => "ml",
+            Unit::Liter => "l",
+            Unit::Deciliter => "dl",
+            Unit::Teaspoon => "tsp",
+            Unit::Tablespoon => "tbsp",
+            Unit::FluidOunce => "fl oz",
+            Unit::Gill => "gill",
+            Unit::Cup => "c",
+            Unit::Pint => "pt",
+            Unit::Quart => "qt",
+            Unit::Gallon => "gal",
+            Unit::Milligram => "mg",
+            Unit::Gram => "g",
+            Unit::Kilogram => "kg",
+            Unit::Ounce => "oz",
+            Unit::Pound => "lb",
+            Unit::ImperialFluidOunce => "imp fl oz",
+            Unit::ImperialGill => "imp gill",
+            Unit::ImperialPint => "imp pt",
+            Unit::ImperialQuart => "imp qt",
+            Unit::ImperialGallon => "imp gal",
+            Unit::LegalCup => "legal c",
+            Unit::LegalFluidOunce => "legal fl oz",
+            Unit::MetricCup => "metric c",
+            Unit::MetricTeaspoon => "metric tsp",
+            Unit::MetricTablespoon => "metric tbsp",
+            Unit::AustralianTablespoon => "AU tbsp",
+            _ => self.long_name(),
+        }
+    }
+    pub fn long_name(&self) -> &'static str {
+        match self.unit {
+            Unit::Milliliter => "milliliter",
+            Unit::Liter => "liter",
+            Unit::Deciliter => "deciliter",
+            Unit::Teaspoon => "teaspoon",
+            Unit::Tablespoon => "tablespoon",
+            Unit::FluidOunce => "fluid ounce",
+            Unit::Gill => "gill",
+            Unit::Cup => "cup",
+            Unit::Pint => "pint",
+            Unit::Quart => "quart",
+            Unit::Gallon => "gallon",
+            Unit::Milligram => "milligram",
+            Unit::Gram => "gram",
+            Unit::Kilogram => "kilogram",
+            Unit::Ounce => "ounce",
+            Unit::Pound => "pound",
+            Unit::Drop => "drop",
+            Unit::Smidgen => "smidgen",
+            Unit::Pinch => "pinch",
+            Unit::Dash => "dash",
+            Unit::Tad => "tad",
+            Unit::ImperialFluidOunce => "imperial fluid ounce",
+            Unit::ImperialGill => "imperial gill",
+            Unit::ImperialPint => "imperial pint",
+            Unit::ImperialQuart => "imperial quart",
+            Unit::ImperialGallon => "imperial gallon",
+            Unit::LegalCup => "legal cup",
+            Unit::LegalFluidOunce => "legal fluid ounce",
+            Unit::MetricCup => "metric cup",
+            Unit::MetricTeaspoon => "metric teaspoon",
+            Unit::MetricTablespoon => "metric tablespoon",
+            Unit::AustralianTablespoon => "Australian tablespoon",
+            Unit::Can => "can",
+            Unit::Jar => "jar",
+            Unit::Bottle => "bottle",
+            Unit::Package => "package",
+            Unit::Box => "box",
+            Unit::Bag => "bag",
+            Unit::Stick => "stick",
+            Unit::Clove => "clove",
+            Unit::Bunch => "bunch",
+            Unit::Head => "head",
+            Unit::Sprig => "sprig",
+            Unit::Slice => "slice",
+            Unit::Piece => "piece",
+            Unit::Batch => "batch",
+            Unit::Fahrenheit => "°F",
+            Unit::Celsius => "°C",
+            Unit::GasMark => "gas mark",
+            Unit::Custom | Unit::Other => self.names[0],
+        }
+    }
+}
+
+impl RecipeItem {
+    pub fn format(&self, fmt: &QuantityFormat) -> String {
+        fmt.item(self)
+    }
+}
+
+impl ParsedRecipe {
+    pub fn format(&self, fmt: &QuantityFormat) -> String {
+        let mut string = String::new();
+        string.push_str("\n\n\t");
+        string.push_str(&self.text.title);
+        string.push_str("\n\t");
+        string.push_str("By: ");
+        string.push_str(&self.text.author_name);
+        string.push_str("\n\n");
+
+        string.push_str("From: ");
+        string.push_str(&self.text.origin);
+        string.push_str("\n\n");
+
+        string.push_str(&self.text.description);
+        string.push_str("\n\n");
+
+        for (name, time) in self.text.times.named() {
+            if let Some(time) = time {
+                string.push_str(&format!("{}{} Time: {}\n", name[..1].to_uppercase(), &name[1..], format_duration(time)));
+            }
+        }
+
+        string.push_str("Servings: ");
+        string.push_str(&fmt.quantity(self.data.servings));
+        string.push_str("\n\n");
+
+        string.push_str("Ingredients:\n");
+        let c = self.data.ingredients.len().to_string().len() + 2;
+        let groups = self.data.ingredient_groups.iter().enumerate().map(|(g, group)| {
+            // Point to the directions that make the part, ex: "For the gravy (see Make the gravy)"
+            let section = self.data.group_section(g).map(|s| self.data.directions[s].name.as_str());
+            let name = match section {
+                Some(s) if !s.is_empty() && s != group.name => format!("{} (see {})", group.name, s),
+                _ => group.name.clone(),
+            };
+            (name, group.ingredients.clone())
+        });
+        for (name, indices) in std::iter::once((String::new(), self.data.ungrouped_ingredients())).chain(groups) {
+            if !name.is_empty() {
+                string.push_str(&format!("{} {}\n", " ".repeat(c - 2), name));
+            }
+            // Groups can be left pointing past the ingredients by hand edits
+            for i in indices {
+                if let Some(item) = self.data.ingredients.get(i) {
+                    string.push_str(&format!("{: >n$}) {}\n", i, fmt.item(item), n = c));
+                }
+            }
+        }
+
+        string.push('\n');
+        string.push_str("Directions: \n");
+        let c = self.data.directions.len().to_string().len() + 2;
+        for section in self.data.directions.iter() {
+            string.push_str(&format!("{} {}\n", " ".repeat(c - 2), section.name));
+            for (i, direction) in section.sections.iter().enumerate() {
+                string.push_str(&format!("{: >n$}) {}\n", i, direction, n = c * 2));
+            }
+        }
+
+        string.push_str("\nServing Size: ");
+        string.push_str(&fmt.quantity(self.data.nutrition_info.servings_size));
+        string.push(' ');
+        string.push_str(&self.data.nutrition_info.servings_unit.to_string());
+        string.push_str("\n\n");
+
+        string.push_str(match self.data.nutrition_info.basis {
+            NutritionBasis::PerServing => "Nutrition Info (per serving):\n",
+            NutritionBasis::PerRecipe => "Nutrition Info (per recipe):\n",
+        });
+        for nutrient in self.data.nutrition_info.nutrients.iter() {
+            string.push_str(&format!(
+                "  {} {}{}\n",
+                nutrient.nutrient.label(),
+                fmt.quantity(nutrient.amount),
+                nutrient.unit.symbol()
+            ));
+        }
+
+        string.replace('"', "").replace(" null", "")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DirectionSection, DirectionStep, IngredientGroup};
+
+    #[test]
+    fn default_display_uses_long_unit_names() {
+        let item = RecipeItem::parse("2 cups milk", None).unwrap();
+        assert_eq!(item.to_string().trim(), "2 cups milk");
+        let short = QuantityFormat {
+            unit_names: UnitNames::Short,
+            ..Default::default()
+        };
+        assert_eq!(short.item(&item).trim(), "2 c milk");
+    }
+
+    #[test]
+    fn names_follow_the_count() {
+        let eggs = RecipeItem::parse("2 eggs", None).unwrap();
+        assert_eq!((eggs.clone() * MixedRational::fract(1, 2)).to_string().trim(), "1 egg");
+        assert_eq!((eggs.clone() * MixedRational::fract(1, 4)).to_string().trim(), "1/2 egg");
+        assert_eq!((eggs * MixedRational::whole(2)).to_string().trim(), "4 eggs");
+        let tomatoes = RecipeItem::parse("3 tomatoes", None).unwrap();
+        assert_eq!((tomatoes * MixedRational::fract(1, 3)).to_string().trim(), "1 tomato");
+        assert_eq!(singularize_phrase("large eggs, beaten"), "large egg, beaten");
+        for word in ["hummus", "couscous", "cherries", "loaves", "peaches", "boxes"] {
+            assert_eq!(pluralize(&singularize(word)), word);
+        }
+    }
+
+    #[test]
+    fn groups_show_their_section_and_skip_stale_indices() {
+        let mut recipe = ParsedRecipe::default();
+        recipe.data.ingredients = vec![
+            RecipeItem::parse("1 cup rice", None).unwrap(),
+            RecipeItem::parse("2 tbsp flour", None).unwrap(),
+        ];
+        recipe.data.directions = vec![
+            DirectionSection { name: "Cook the rice".into(), sections: vec![DirectionStep::new("Boil it".into())] },
+            DirectionSection { name: "Make the gravy".into(), sections: vec![DirectionStep::new("Whisk".into())] },
+        ];
+        recipe.data.ingredient_groups = vec![IngredientGroup {
+            name: "For the gravy".into(),
+            ingredients: vec![1, 7],
+            section: None,
+        }];
+        let text = recipe.to_string();
+        assert!(text.contains("For the gravy (see Make the gravy)"), "{}", text);
+        assert!(text.contains("2 tablespoons flour"), "{}", text);
+        assert!(!text.contains("7)"), "{}", text);
+    }
+}
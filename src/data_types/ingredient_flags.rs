@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+
+use crate::RecipeItem;
+
+/// How an ingredient is used besides its amount
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IngredientFlag {
+    Optional,
+    ToTaste,
+    Garnish,
+    /// Used in more than one step, ex: "2 cups sugar, divided"
+    Divided,
+}
+
+// Longer phrases first so "for garnish" isn't left as "for"
+#[rustfmt::skip]
+const FLAG_PHRASES: &[(&str, IngredientFlag)] = &[
+    ("for garnishing", IngredientFlag::Garnish),
+    ("for garnish", IngredientFlag::Garnish),
+    ("to garnish", IngredientFlag::Garnish),
+    ("garnish", IngredientFlag::Garnish),
+    ("to taste", IngredientFlag::ToTaste),
+    ("as needed", IngredientFlag::ToTaste),
+    ("if desired", IngredientFlag::Optional),
+    ("optional", IngredientFlag::Optional),
+    ("divided", IngredientFlag::Divided),
+];
+
+// Left hanging once a phrase is taken out, ex: the "plus more" of "oil, plus more as needed"
+const CONNECTORS: &[&str] = &["or", "and", "plus", "more", "-"];
+
+impl IngredientFlag {
+    pub fn phrase(&self) -> &'static str {
+        match self {
+            IngredientFlag::Optional => "optional",
+            IngredientFlag::ToTaste => "to taste",
+            IngredientFlag::Garnish => "for garnish",
+            IngredientFlag::Divided => "divided",
+        }
+    }
+}
+
+/// Take the flag phrases out of `text`, ex: "salt and pepper to taste" -> ("salt and pepper", [ToTaste])
+pub fn extract_flags(text: &str) -> (String, Vec<IngredientFlag>) {
+    let words = text.split_whitespace().collect::<Vec<_>>();
+    let bare = words
+        .iter()
+        .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+        .collect::<Vec<_>>();
+    let (mut kept, mut flags) = (Vec::new(), Vec::new());
+    let phrase_at = |at: usize| {
+        FLAG_PHRASES.iter().find(|(phrase, _)| {
+            let len = phrase.split(' ').count();
+            bare.get(at..at + len).is_some_and(|w| w.join(" ") == *phrase)
+        })
+    };
+    let mut i = 0;
+    while i < words.len() {
+        // Extra on top of the amount rather than how it's used, ex: "1/2 cup flour, plus more as needed"
+        let more = match (bare.get(i).map(String::as_str), bare.get(i + 1).map(String::as_str)) {
+            (Some("plus"), Some("more")) => 2,
+            (Some("more"), _) => 1,
+            _ => 0,
+        };
+        if let Some((phrase, _)) = phrase_at(i + more).filter(|_| more > 0) {
+            i += more + phrase.split(' ').count();
+            continue;
+        }
+        if let Some((phrase, flag)) = phrase_at(i) {
+            if !flags.contains(flag) {
+                flags.push(*flag);
+            }
+            i += phrase.split(' ').count();
+            continue;
+        }
+        kept.push(words[i]);
+        i += 1;
+    }
+    let is_connector = |w: &&str| CONNECTORS.contains(&w.trim_matches(',').to_lowercase().as_str());
+    while kept.last().is_some_and(is_connector) {
+        kept.pop();
+    }
+    while kept.first().is_some_and(is_connector) {
+        kept.remove(0);
+    }
+    let text = kept.join(" ").trim_matches([',', ' ']).to_string();
+    (text, flags)
+}
+
+impl RecipeItem {
+    pub fn has_flag(&self, flag: IngredientFlag) -> bool {
+        self.flags.contains(&flag)
+    }
+    /// Whether there's an amount to scale or buy, "salt, to taste" has none
+    pub fn is_measured(&self) -> bool {
+        !self.measure.count.is_zero()
+    }
+    /// Things to have on hand rather than buy an amount of, ex: salt to taste, parsley for garnish,
+    /// anything with an amount is bought
+    pub fn buy_if_missing(&self) -> bool {
+        !self.is_measured()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plus_more_is_not_a_flag() {
+        assert_eq!(extract_flags("flour, plus more as needed"), ("flour".into(), vec![]));
+        assert_eq!(extract_flags("oil, more to taste"), ("oil".into(), vec![]));
+        let salt = extract_flags("salt and pepper to taste");
+        assert_eq!(salt, ("salt and pepper".into(), vec![IngredientFlag::ToTaste]));
+        assert_eq!(extract_flags("sugar, divided"), ("sugar".into(), vec![IngredientFlag::Divided]));
+    }
+
+    #[test]
+    fn measured_items_are_bought() {
+        let flour = RecipeItem::parse("1/2 cup flour, plus more as needed", None).unwrap();
+        assert!(flour.flags.is_empty());
+        assert!(!flour.buy_if_missing());
+        let salt = RecipeItem::parse("salt, to taste", None).unwrap();
+        assert!(salt.buy_if_missing());
+    }
+}
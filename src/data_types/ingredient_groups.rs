@@ -0,0 +1,207 @@
+use crate::{normalize_ingredient_name, MixedRational, RecipeData, RecipeItem};
+
+// Words every component heading shares, ex: "For the gravy" and "Make the gravy"
+#[rustfmt::skip]
+const GENERIC_WORDS: &[&str] = &[
+    "for", "the", "a", "an", "and", "of", "to", "make", "making", "prepare", "preparing", "assemble",
+    "your", "ingredient", "ingredients",
+];
+
+/// Ingredients that make up one part of a recipe, ex: "For the gravy"
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct IngredientGroup {
+    pub name: String,
+    /// Indices into the recipe's ingredients
+    pub ingredients: Vec<usize>,
+    /// Index of the direction section that makes this part, see `RecipeData::group_section`
+    #[serde(default)]
+    pub section: Option<usize>,
+}
+
+/// The group name if `line` starts a group rather than being an ingredient, ex: "For the sauce:"
+pub fn group_heading(line: &str) -> Option<String> {
+    let line = line.trim().trim_matches('"').trim();
+    let name = line.trim_end_matches(':').trim();
+    let short = !name.is_empty() && name.split_whitespace().count() <= 6;
+    let counted = name.starts_with(char::is_numeric);
+    let heading = line.ends_with(':') || name.to_lowercase().starts_with("for the ");
+    (short && !counted && heading).then(|| name.to_string())
+}
+
+// What a heading is about, ex: "For the Gravy" -> ["gravy"]
+fn topic_words(name: &str) -> Vec<String> {
+    normalize_ingredient_name(name)
+        .split(' ')
+        .filter(|w| !w.is_empty() && !GENERIC_WORDS.contains(w))
+        .map(String::from)
+        .collect()
+}
+
+/// Ingredient lines under their headings in a recipe page, for pages whose JSON-LD has the lines
+/// without the headings
+pub fn ingredient_groups_from_html(document: &scraper::Html) -> Vec<(String, Vec<String>)> {
+    let container = scraper::Selector::parse(r#"[class*="ingredients"], [id*="ingredients"]"#).unwrap();
+    let parts = scraper::Selector::parse(r#"h2, h3, h4, h5, h6, p, li, [class*="group-name"]"#).unwrap();
+    let text = |e: scraper::ElementRef| e.text().flat_map(str::split_whitespace).collect::<Vec<_>>().join(" ");
+    let Some(list) = document
+        .select(&container)
+        .find(|c| c.select(&parts).any(|e| e.value().name() == "li"))
+    else {
+        return Vec::new();
+    };
+    let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+    for element in list.select(&parts) {
+        let in_item = element
+            .ancestors()
+            .any(|a| a.value().as_element().is_some_and(|e| e.name() == "li"));
+        if element.value().name() == "li" {
+            let line = text(element);
+            if line.is_empty() {
+                continue;
+            }
+            match groups.last_mut() {
+                Some((_, lines)) => lines.push(line),
+                None => groups.push((String::new(), vec![line])),
+            }
+        } else if !in_item {
+            let name = text(element);
+            let name = name.trim_end_matches(':').trim();
+            if !name.is_empty() && name.split_whitespace().count() <= 6 {
+                groups.push((name.to_string(), Vec::new()));
+            }
+        }
+    }
+    groups.retain(|(_, lines)| !lines.is_empty());
+    groups
+}
+
+impl RecipeData {
+    pub fn ingredient_group(&self, index: usize) -> Option<&IngredientGroup> {
+        self.ingredient_groups.iter().find(|g| g.ingredients.contains(&index))
+    }
+    /// Indices of ingredients in no group, listed before the groups
+    pub fn ungrouped_ingredients(&self) -> Vec<usize> {
+        (0..self.ingredients.len())
+            .filter(|i| self.ingredient_group(*i).is_none())
+            .collect()
+    }
+    /// The direction section that makes group `group`, the one saved with it if still there, otherwise
+    /// by name first, ex: "Make the gravy" for "For the gravy", then by which section uses most of its
+    /// ingredients
+    pub fn group_section(&self, group: usize) -> Option<usize> {
+        let saved = self.ingredient_groups.get(group)?.section;
+        saved.filter(|s| *s < self.directions.len()).or_else(|| self.find_group_section(group))
+    }
+    fn find_group_section(&self, group: usize) -> Option<usize> {
+        let group = self.ingredient_groups.get(group)?;
+        let topic = topic_words(&group.name);
+        let shared = |name: &str| topic_words(name).iter().filter(|w| topic.contains(w)).count();
+        let by_name = (0..self.directions.len())
+            .map(|s| (shared(&self.directions[s].name), s))
+            .filter(|(n, _)| *n > 0)
+            .max_by_key(|(n, s)| (*n, std::cmp::Reverse(*s)));
+        if let Some((_, section)) = by_name {
+            return Some(section);
+        }
+        let uses = |s: usize| {
+            group
+                .ingredients
+                .iter()
+                .filter(|i| self.directions[s].sections.iter().any(|step| step.ingredients.contains(i)))
+                .count()
+        };
+        (0..self.directions.len())
+            .map(|s| (uses(s), s))
+            .filter(|(n, _)| *n > 0)
+            .max_by_key(|(n, s)| (*n, std::cmp::Reverse(*s)))
+            .map(|(_, s)| s)
+    }
+    /// Save the direction section of every group so it's shown and exported with it
+    pub fn align_group_sections(&mut self) {
+        for group in 0..self.ingredient_groups.len() {
+            self.ingredient_groups[group].section = self.find_group_section(group);
+        }
+    }
+    /// Scale one part of the recipe, ex: double the sauce, along with the steps that make it
+    pub fn scale_group(&self, group: usize, factor: MixedRational) -> Self {
+        let mut new = self.clone();
+        let Some(g) = self.ingredient_groups.get(group) else {
+            return new;
+        };
+        for i in g.ingredients.iter() {
+            if let Some(item) = new.ingredients.get_mut(*i) {
+                *item = item.clone() * factor;
+            }
+        }
+        if let Some(section) = self.group_section(group) {
+            new.directions[section] = new.directions[section].clone() * factor;
+        }
+        new
+    }
+    /// Group the ingredients by the headings of the recipe page, left alone when the page lists a
+    /// different number of ingredients
+    pub fn group_ingredients_from_html(&mut self, document: &scraper::Html) {
+        let html = ingredient_groups_from_html(document);
+        if !self.ingredient_groups.is_empty() || html.iter().all(|(name, _)| name.is_empty()) {
+            return;
+        }
+        let mut groups = Vec::new();
+        let mut next = 0;
+        for (name, lines) in html {
+            let count = lines
+                .iter()
+                .filter(|l| group_heading(l).is_none() && RecipeItem::parse(l, None).is_some())
+                .count();
+            if !name.is_empty() {
+                groups.push(IngredientGroup {
+                    name,
+                    ingredients: (next..next + count).collect(),
+                    section: None,
+                });
+            }
+            next += count;
+        }
+        if next == self.ingredients.len() {
+            self.ingredient_groups = groups;
+            self.align_group_sections();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DirectionSection, DirectionStep};
+
+    fn gravy_recipe() -> RecipeData {
+        RecipeData {
+            ingredients: vec![RecipeItem::parse("2 tbsp flour", None).unwrap()],
+            directions: vec![
+                DirectionSection { name: "Cook the rice".into(), sections: vec![DirectionStep::new("Boil".into())] },
+                DirectionSection { name: "Make the gravy".into(), sections: vec![DirectionStep::new("Whisk".into())] },
+            ],
+            ingredient_groups: vec![IngredientGroup { name: "For the gravy".into(), ingredients: vec![0], section: None }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn aligned_section_is_exported() {
+        let mut data = gravy_recipe();
+        data.align_group_sections();
+        let json = serde_json::to_value(&data.ingredient_groups[0]).unwrap();
+        assert_eq!(json["section"], 1);
+        // Groups saved before sections were stored still load
+        let old: IngredientGroup = serde_json::from_str(r#"{"name": "For the gravy", "ingredients": [0]}"#).unwrap();
+        assert_eq!(old.section, None);
+    }
+
+    #[test]
+    fn saved_section_wins_unless_stale() {
+        let mut data = gravy_recipe();
+        data.ingredient_groups[0].section = Some(0);
+        assert_eq!(data.group_section(0), Some(0));
+        data.ingredient_groups[0].section = Some(5);
+        assert_eq!(data.group_section(0), Some(1));
+    }
+}
@@ -0,0 +1,228 @@
+use std::{collections::HashMap, sync::OnceLock};
+
+use crate::{is_number, normalize_ingredient_name, Measure, ParsedRecipe, RecipeItem, Unit};
+
+// Canonical name and what else it's called
+#[rustfmt::skip]
+const BUNDLED_SYNONYMS: &[(&str, &[&str])] = &[
+    ("green onion", &["scallion", "spring onion"]),
+    ("cilantro", &["coriander", "coriander leaves", "cilantro leaves", "chinese parsley"]),
+    ("parsley", &["parsley leaves", "flat leaf parsley", "italian parsley"]),
+    ("basil", &["basil leaves"]),
+    ("mint", &["mint leaves"]),
+    ("all-purpose flour", &["flour", "plain flour", "ap flour"]),
+    ("granulated sugar", &["sugar", "white sugar"]),
+    ("powdered sugar", &["confectioners sugar", "icing sugar"]),
+    ("baking soda", &["bicarbonate of soda", "bicarb soda"]),
+    ("cornstarch", &["corn starch", "cornflour"]),
+    ("heavy cream", &["heavy whipping cream", "whipping cream", "double cream"]),
+    ("eggplant", &["aubergine"]),
+    ("zucchini", &["courgette"]),
+    ("bell pepper", &["capsicum", "sweet pepper"]),
+    ("arugula", &["rocket"]),
+    ("chickpea", &["garbanzo bean", "garbanzo"]),
+    ("shrimp", &["prawn"]),
+    ("ground beef", &["minced beef", "beef mince"]),
+    ("black pepper", &["pepper", "ground black pepper", "ground pepper"]),
+    ("red pepper flakes", &["crushed red pepper", "chili flakes", "chile flakes", "red chili flakes"]),
+    ("ginger", &["ginger root", "gingerroot"]),
+    ("parmesan", &["parmesan cheese", "parmigiano reggiano", "parmigiano"]),
+    ("olive oil", &["evoo"]),
+    ("soy sauce", &["soya sauce"]),
+    ("egg", &["whole egg"]),
+];
+
+// Words that say which kind of an ingredient, ex: boneless, large, low-sodium
+#[rustfmt::skip]
+const DESCRIPTOR_WORDS: &[&str] = &[
+    "boneless", "skinless", "fresh", "large", "small", "medium", "extra", "jumbo", "virgin", "unsalted",
+    "salted", "low", "reduced", "sodium", "fat", "free", "lean", "organic", "ripe", "raw", "cooked",
+    "uncooked", "frozen", "dried", "canned", "good", "quality", "high", "homemade", "store", "bought",
+    "unsweetened", "sweetened", "plain", "cold", "warm", "lukewarm", "room", "temperature", "heaping",
+    "packed", "thick", "thin", "whole",
+];
+
+// Words that say what to do to it, ex: chopped, cut, thinly
+#[rustfmt::skip]
+const PREPARATION_WORDS: &[&str] = &[
+    "chopped", "diced", "minced", "sliced", "grated", "shredded", "crushed", "cubed", "peeled", "pitted",
+    "trimmed", "halved", "quartered", "beaten", "melted", "softened", "juiced", "zested", "drained",
+    "rinsed", "divided", "pounded", "cut", "torn", "crumbled", "mashed", "julienned", "seeded", "cored",
+    "deveined", "toasted", "sifted", "separated", "whisked", "thawed", "cooled", "finely", "roughly",
+    "thinly", "coarsely", "freshly", "lightly",
+];
+
+/// A raw ingredient name split up, ex: "boneless skinless chicken breasts, cut into 1 inch pieces"
+/// is a chicken breast, boneless and skinless, cut into 1 inch pieces
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IngredientName {
+    pub canonical: String,
+    pub descriptors: Vec<String>,
+    pub preparation: Option<String>,
+}
+
+impl std::fmt::Display for IngredientName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.canonical)?;
+        if !self.descriptors.is_empty() {
+            write!(f, " ({})", self.descriptors.join(", "))?;
+        }
+        if let Some(preparation) = &self.preparation {
+            write!(f, ", {}", preparation)?;
+        }
+        Ok(())
+    }
+}
+
+/// Canonical ingredient names and their synonyms, keyed by normalized synonym
+#[derive(Clone, Debug, Default)]
+pub struct IngredientCatalog {
+    pub synonyms: HashMap<String, String>,
+}
+
+impl IngredientCatalog {
+    pub fn bundled() -> Self {
+        let mut catalog = IngredientCatalog::default();
+        for (canonical, synonyms) in BUNDLED_SYNONYMS {
+            catalog.insert(canonical, synonyms);
+        }
+        catalog
+    }
+    /// The bundled catalog, built once and shared
+    pub fn shared() -> &'static Self {
+        static CATALOG: OnceLock<IngredientCatalog> = OnceLock::new();
+        CATALOG.get_or_init(IngredientCatalog::bundled)
+    }
+    pub fn insert(&mut self, canonical: &str, synonyms: &[&str]) {
+        for name in std::iter::once(canonical).chain(synonyms.iter().copied()) {
+            self.synonyms.insert(normalize_ingredient_name(name), canonical.to_string());
+        }
+    }
+    /// Add entries from a JSON object of canonical name to synonyms, ex: {"green onion": ["scallion"]}
+    pub fn extend_from_file<P>(&mut self, path: P) -> Result<(), Box<dyn std::error::Error>>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let entries: HashMap<String, Vec<String>> = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        for (canonical, synonyms) in entries {
+            self.insert(&canonical, &synonyms.iter().map(String::as_str).collect::<Vec<_>>());
+        }
+        Ok(())
+    }
+    pub fn parse(&self, raw: &str) -> IngredientName {
+        let (head, rest) = raw.split_at(raw.find([',', '(']).unwrap_or(raw.len()));
+        let tokens = head
+            .split_whitespace()
+            .map(|t| t.trim_matches(|c: char| !c.is_alphanumeric() && c != '-').to_lowercase())
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>();
+        let mut i = 0;
+        // Quantities left in names by older files, ex: "pounds boneless chicken", "pinch of salt"
+        while i + 1 < tokens.len() && (is_unit_word(&tokens[i]) || is_number(&tokens[i]) || tokens[i] == "of") {
+            i += 1;
+        }
+        let (mut descriptors, mut preparation) = (Vec::new(), Vec::new());
+        let mut leading = Vec::new();
+        while i + 1 < tokens.len() {
+            let token = &tokens[i];
+            if is_descriptor(token) {
+                descriptors.push(token.clone());
+            } else if PREPARATION_WORDS.contains(&token.as_str()) {
+                leading.push(token.as_str());
+            } else if !(["and", "or"].contains(&token.as_str()) && i > 0 && !descriptors.is_empty()) {
+                break;
+            }
+            i += 1;
+        }
+        if !leading.is_empty() {
+            preparation.push(leading.join(" "));
+        }
+        // The name runs until what's done to it, ex: "chicken breasts pounded thin"
+        let end = (i + 1..tokens.len())
+            .find(|j| PREPARATION_WORDS.contains(&tokens[*j].as_str()))
+            .unwrap_or(tokens.len());
+        if end < tokens.len() {
+            preparation.push(tokens[end..].join(" "));
+        }
+        let rest = rest.replace(['(', ')'], " ");
+        let rest = rest.trim_start_matches([',', ' ']).split_whitespace().collect::<Vec<_>>().join(" ");
+        if !rest.is_empty() {
+            preparation.push(rest);
+        }
+        let core = tokens.get(i..end).filter(|c| !c.is_empty()).unwrap_or(&tokens[..]);
+        IngredientName {
+            canonical: self.canonical_of(core),
+            descriptors,
+            preparation: (!preparation.is_empty()).then(|| preparation.join(", ")),
+        }
+    }
+    // The catalog's name, or the words with the last one singular, ex: "cherry tomatoes" -> "cherry tomato"
+    fn canonical_of(&self, words: &[String]) -> String {
+        let joined = words.join(" ");
+        if let Some(canonical) = self.synonyms.get(&normalize_ingredient_name(&joined)) {
+            return canonical.clone();
+        }
+        match words.split_last() {
+            Some((last, first)) => first
+                .iter()
+                .cloned()
+                .chain(std::iter::once(singular(last)))
+                .collect::<Vec<_>>()
+                .join(" "),
+            None => joined,
+        }
+    }
+    pub fn canonical(&self, raw: &str) -> String {
+        self.parse(raw).canonical
+    }
+    /// Two names with the same key are the same ingredient, ex: "Scallions, sliced" and "green onion"
+    pub fn key(&self, raw: &str) -> String {
+        normalize_ingredient_name(&self.canonical(raw))
+    }
+}
+
+fn is_unit_word(token: &str) -> bool {
+    token.len() > 1 && Measure::new(token.to_string()).unit != Unit::Other
+}
+
+// Hyphenated descriptors count when every part is one, ex: low-sodium, fat-free
+fn is_descriptor(token: &str) -> bool {
+    token.split('-').all(|part| DESCRIPTOR_WORDS.contains(&part))
+}
+
+fn singular(word: &str) -> String {
+    if word.len() <= 3 || word.ends_with("ss") || word.ends_with("us") || word.ends_with("is") {
+        return word.to_string();
+    }
+    if word == "leaves" {
+        return "leaf".into();
+    }
+    if let Some(stem) = word.strip_suffix("ies") {
+        return format!("{}y", stem);
+    }
+    if let Some(stem) = word.strip_suffix("oes") {
+        return format!("{}o", stem);
+    }
+    if ["ches", "shes", "xes"].iter().any(|s| word.ends_with(s)) {
+        return word[..word.len() - 2].to_string();
+    }
+    word.strip_suffix('s').unwrap_or(word).to_string()
+}
+
+impl RecipeItem {
+    pub fn ingredient_name(&self) -> IngredientName {
+        IngredientCatalog::shared().parse(&self.name)
+    }
+    pub fn canonical_name(&self) -> String {
+        self.ingredient_name().canonical
+    }
+}
+
+impl ParsedRecipe {
+    /// Whether any ingredient is `ingredient` by canonical name, ex: "scallions" finds "green onions, sliced"
+    pub fn uses_ingredient(&self, ingredient: &str) -> bool {
+        let catalog = IngredientCatalog::shared();
+        let key = catalog.key(ingredient);
+        self.data.ingredients.iter().any(|i| catalog.key(&i.name) == key)
+    }
+}
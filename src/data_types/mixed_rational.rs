@@ -5,77 +5,79 @@ use std::fmt::{self, Display};
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct MixedRational {
     #[serde(skip_serializing_if = "MixedRational::skip", default="MixedRational::zero")]
-    pub value: i32,
+    pub value: i64,
     #[serde(skip_serializing_if = "MixedRational::skip", default="MixedRational::zero")]
-    pub num: i32,
+    pub num: i64,
     #[serde(skip_serializing_if = "MixedRational::skip", default="MixedRational::zero")]
-    pub den: u32,
+    pub den: u64,
 }
 
 
 impl MixedRational {
+    /// Lenient parse used while scraping, anything that isn't a quantity becomes zero
     pub fn from_string(s: String) -> (Self, Option<Self>) {
-        fn replace_unicode(s: String) -> String {
-            let mut new_s = s;
-            for (c, new) in "¼½¾⅐⅑⅒⅓⅔⅕⅖⅗⅘⅙⅚⅛⅜⅝⅞".chars().zip(
-                ["1/4", "1/2", "3/4", "1/7", "1/9", "1/10", "1/3", "2/3", "1/5", 
-                 "2/5", "3/5", "4/5", "1/6", "5/6", "1/8",  "3/8", "5/8", "7/8"]) {
-                new_s = new_s.replace(c, &format!(" {}", new))
-            }
-            new_s
-        }
         let valid = Self::valid_chars();
-        let mut txt = replace_unicode(s.to_lowercase().chars().filter(|x| valid.contains(*x) || valid.contains("to")).collect());
-        if let Some(period) = txt.find('.') {
-            let ( l,  r) = txt.split_at(period);
-            let value = l.replace(|c: char| !c.is_ascii_digit(), "");
-            let decimal = r.replace(|c: char| !c.is_ascii_digit(), "");
-            let dec_l = decimal.len() as u32;
-            return (MixedRational::new(value.parse::<i32>().unwrap(), decimal.parse::<i32>().unwrap(), 10u32.pow(dec_l)), None);
-        }
-        // Fix spaces :)
-        txt = txt.trim().split(' ')
-            .filter(|s| !s.is_empty())
+        // Drop words like "servings" but keep the range words
+        let txt = s.to_lowercase()
+            .split_whitespace()
+            .filter_map(|word| {
+                if RANGE_WORDS.contains(&word) {
+                    Some(word.to_string())
+                } else {
+                    let w: String = word.chars().filter(|c| valid.contains(*c)).collect();
+                    (!w.is_empty()).then_some(w)
+                }
+            })
             .collect::<Vec<_>>()
             .join(" ");
-        if txt.is_empty() {return (Self::default(), None)}
-        while txt.as_bytes()[0] == b' ' {
-            txt.remove(0);
-        }
-
-        let (a, b): (String, String) = if let Some(dash) = txt.find('-') {
-            let (a,b) = txt.split_at(dash);
-            (a.into(), b[1..].into())
-        } else if let Some(to) = txt.find("to") {
-            let (a,b) = txt.split_at(to);
-            (a.into(), b[2..].into())
-        } else {
-            (txt, "".into())
-        };
-        if let Some(f) = parse_fract(a) {
-            (f, parse_fract(b))
+        Self::try_from_string(&txt).unwrap_or((Self::default(), None))
+    }
+    /// Parses a quantity or a range of quantities, ex: "1 1/2", "1½", "2.5", "2-3", "1 to 2"
+    pub fn try_from_string(s: &str) -> Result<(Self, Option<Self>), ParseRationalError> {
+        let range = s.char_indices()
+            // A leading dash is a sign, not a range
+            .filter(|(i, _)| s[..*i].chars().any(|c| !c.is_whitespace()))
+            .find_map(|(i, c)| {
+                if RANGE_DASHES.contains(&c) {
+                    return Some((i, c.len_utf8(), true));
+                }
+                RANGE_WORDS.iter()
+                    .map(|w| format!(" {} ", w))
+                    .find(|w| s[i..].to_ascii_lowercase().starts_with(w.as_str()))
+                    .map(|w| (i, w.len(), false))
+            });
+        if let Some((at, len, dash)) = range {
+            // "1--2" is a typo, not a range down to -2
+            let rest = s[at + len..].trim_start();
+            if dash && rest.starts_with(RANGE_DASHES) {
+                let pos = s.len() - rest.len();
+                let token = rest.split_whitespace().next().unwrap_or(rest);
+                return Err(ParseRationalError::new(pos, token, ParseRationalErrorKind::UnexpectedToken));
+            }
+            let upper = parse_quantity(&s[at + len..], at + len)?;
+            Ok((parse_quantity(&s[..at], 0)?, Some(upper)))
         } else {
-            (MixedRational::whole(0), None)
+            Ok((parse_quantity(s, 0)?, None))
         }
     }
     pub fn valid_chars() -> String {
-        "-1234567890 /¼½¾⅐⅑⅒⅓⅔⅕⅖⅗⅘⅙⅚⅛⅜⅝⅞.".into()
+        "-–1234567890 /⁄¼½¾⅐⅑⅒⅓⅔⅕⅖⅗⅘⅙⅚⅛⅜⅝⅞.".into()
     }
-    pub fn new(w: i32, n: i32, d: u32) -> Self {
+    pub fn new(w: i64, n: i64, d: u64) -> Self {
         MixedRational {
             value: w,
             num: n,
             den: d,
         }.simplify()
     }
-    pub fn fract(n: i32, d: u32) -> Self {
+    pub fn fract(n: i64, d: u64) -> Self {
         MixedRational {
             value: 0,
             num: n,
             den: d,
         }.simplify()
     }
-    pub fn whole(v: i32) -> Self {
+    pub fn whole(v: i64) -> Self {
         MixedRational {
             value: v,
             num: 0,
@@ -83,56 +85,170 @@ impl MixedRational {
         }
     }
 
-    pub fn from_frac_list(l: Vec<[i32;2]>) -> Vec<Self> {
-        l.iter().map(|f| MixedRational::fract(f[0], f[1] as u32)).collect()
+    pub fn from_frac_list(l: Vec<[i64;2]>) -> Vec<Self> {
+        l.iter().map(|f| MixedRational::fract(f[0], f[1] as u64)).collect()
     }
     pub fn to_float(self) -> f32 {
         self.into()
     }
-    fn gcd32(a: i32, b: i32) -> i32 {
+    fn gcd(a: i128, b: i128) -> i128 {
         let (mut a, mut b) = (a, b);
-        let mut r = 1;
-        while r > 0 {
-            r = a % b;
-            a = b;
-            b = r;
+        while b != 0 {
+            (a, b) = (b, a % b);
         }
         a
     }
-    #[rustfmt::skip]
-    pub fn simplify(&self) -> Self {
-        let sign = [1, -1][(self.value < 0) as usize]
-                 * [1, -1][  (self.num < 0) as usize];
-        let mut fract = if self.den != 0 {
-            (self.num.abs() + (self.den as i32 * self.value.abs()), self.den as i32)
+    /// Signed improper fraction (numerator, denominator), the denominator is always positive.
+    /// Intermediate math is done in i128 so that products of two fractions can never overflow
+    pub fn to_improper(self) -> (i128, i128) {
+        let sign = [1, -1][(self.value < 0) as usize] * [1, -1][(self.num < 0) as usize];
+        if self.den != 0 {
+            (sign * ((self.num as i128).abs() + self.den as i128 * (self.value as i128).abs()), self.den as i128)
         } else {
-            (self.value.abs(), 1)
-        };
-        let r = Self::gcd32(fract.0, fract.1);
-        fract.0 /= r;
-        fract.1 /= r;
-        let whole = fract.0 / fract.1;
-        let new_num = fract.0 - (fract.1 * whole);
+            (self.value as i128, 1)
+        }
+    }
+    /// Reduces an improper fraction into a MixedRational, or None if it does not fit
+    #[rustfmt::skip]
+    pub fn checked_from_improper(n: i128, d: i128) -> Option<Self> {
+        if d == 0 {
+            return None;
+        }
+        let sign = [1, -1][((n < 0) != (d < 0)) as usize];
+        let (n, d) = (n.checked_abs()?, d.checked_abs()?);
+        let r = Self::gcd(n, d);
+        let (n, d) = (n / r, d / r);
+        let whole = i64::try_from(n / d).ok()?;
+        let new_num = i64::try_from(n % d).ok()?;
         if new_num == 0 {
-            return MixedRational {
-                value: whole,
-                num: 0,
-                den: 0,
-            };
-        } 
+            return Some(MixedRational::whole(whole * sign));
+        }
+        let den = u64::try_from(d).ok()?;
         if whole == 0 {
-            return MixedRational {
-                value: 0,
-                num: new_num * sign,
-                den: fract.1 as u32,
-            };
+            return Some(MixedRational { value: 0, num: new_num * sign, den });
         }
-        MixedRational {
-            value: whole,
-            num: new_num,
-            den: fract.1 as u32,
+        Some(MixedRational { value: whole * sign, num: new_num, den })
+    }
+    /// Closest MixedRational to n/d when the exact value doesn't fit, the whole part saturates
+    pub fn approx_from_improper(n: i128, d: i128) -> Self {
+        if let Some(exact) = Self::checked_from_improper(n, d) {
+            return exact;
+        }
+        if d == 0 {
+            return Self::default();
+        }
+        let negative = (n < 0) != (d < 0);
+        let saturated = Self::whole(if negative { -i64::MAX } else { i64::MAX });
+        let (n, d) = (n.unsigned_abs(), d.unsigned_abs());
+        let whole = n / d;
+        if whole > i64::MAX as u128 {
+            return saturated;
+        }
+        let (p, q) = closest_fraction(n % d, d, i64::MAX as u128);
+        let n = (whole * q + p) as i128;
+        Self::checked_from_improper(if negative { -n } else { n }, q as i128).unwrap_or(saturated)
+    }
+    fn approx_from_f64(x: f64) -> Self {
+        if x.is_nan() {
+            return Self::default();
+        }
+        if x.abs() >= i64::MAX as f64 {
+            return Self::whole(if x < 0. { -i64::MAX } else { i64::MAX });
+        }
+        let scale = 1i128 << 32;
+        Self::approx_from_improper((x * scale as f64).round() as i128, scale)
+    }
+    fn to_f64(self) -> f64 {
+        let (n, d) = self.to_improper();
+        n as f64 / d as f64
+    }
+    // (ln / ld) * (rn / rd), cancelling across first so the products stay in range
+    fn approx_product((ln, ld): (i128, i128), (rn, rd): (i128, i128)) -> Self {
+        let (a, b) = (Self::gcd(ln.abs(), rd.abs()).max(1), Self::gcd(rn.abs(), ld.abs()).max(1));
+        let (ln, rd, rn, ld) = (ln / a, rd / a, rn / b, ld / b);
+        match (ln.checked_mul(rn), ld.checked_mul(rd)) {
+            (Some(n), Some(d)) => Self::approx_from_improper(n, d),
+            _ => Self::approx_from_f64((ln as f64 / ld as f64) * (rn as f64 / rd as f64)),
+        }
+    }
+    // (ln / ld) + (rn / rd) over the smallest common denominator
+    fn approx_sum((ln, ld): (i128, i128), (rn, rd): (i128, i128)) -> Self {
+        let common = (ld / Self::gcd(ld, rd)).checked_mul(rd);
+        let sum = common.and_then(|c| {
+            let n = ln.checked_mul(c / ld)?.checked_add(rn.checked_mul(c / rd)?)?;
+            Some(Self::approx_from_improper(n, c))
+        });
+        sum.unwrap_or_else(|| Self::approx_from_f64(ln as f64 / ld as f64 + rn as f64 / rd as f64))
+    }
+    pub fn simplify(&self) -> Self {
+        let (n, d) = self.to_improper();
+        Self::checked_from_improper(n, d).unwrap_or(*self)
+    }
+    pub fn is_zero(&self) -> bool {
+        self.value == 0 && self.num == 0
+    }
+    pub fn is_negative(&self) -> bool {
+        self.value < 0 || self.num < 0
+    }
+    pub fn abs(self) -> Self {
+        if self.is_negative() {
+            -self
+        } else {
+            self
         }
     }
+    /// Largest whole number less than or equal to self
+    pub fn floor(self) -> Self {
+        let (n, d) = self.to_improper();
+        Self::checked_from_improper(n.div_euclid(d), 1).unwrap_or(self)
+    }
+    pub fn checked_neg(self) -> Option<Self> {
+        let (n, d) = self.to_improper();
+        Self::checked_from_improper(n.checked_neg()?, d)
+    }
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        let ((ln, ld), (rn, rd)) = (self.to_improper(), rhs.to_improper());
+        let n = ln.checked_mul(rd)?.checked_add(rn.checked_mul(ld)?)?;
+        Self::checked_from_improper(n, ld.checked_mul(rd)?)
+    }
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.checked_add(rhs.checked_neg()?)
+    }
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        let ((ln, ld), (rn, rd)) = (self.to_improper(), rhs.to_improper());
+        Self::checked_from_improper(ln.checked_mul(rn)?, ld.checked_mul(rd)?)
+    }
+    /// Returns None when dividing by zero as well as on overflow
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        let ((ln, ld), (rn, rd)) = (self.to_improper(), rhs.to_improper());
+        Self::checked_from_improper(ln.checked_mul(rd)?, ld.checked_mul(rn)?)
+    }
+    /// Closest fraction whose denominator is at most `max_den`, using continued fractions
+    pub fn limit_denominator(self, max_den: u64) -> Self {
+        let (n, d) = self.to_improper();
+        let max = max_den.max(1) as i128;
+        if d <= max {
+            return self;
+        }
+        let sign = n.signum();
+        let (mut p0, mut q0, mut p1, mut q1) = (0i128, 1i128, 1i128, 0i128);
+        let (mut rn, mut rd) = (n.abs(), d);
+        while rd != 0 {
+            let a = rn / rd;
+            let q2 = q0 + a * q1;
+            if q2 > max {
+                break;
+            }
+            (p0, q0, p1, q1) = (p1, q1, p0 + a * p1, q2);
+            (rn, rd) = (rd, rn - a * rd);
+        }
+        // The best approximation is either the last convergent or this semiconvergent
+        let k = (max - q0) / q1;
+        let (sn, sd) = (p0 + k * p1, q0 + k * q1);
+        let error = |p: i128, q: i128| (p * d - n.abs() * q).abs() * (sd * q1) / q;
+        let (p, q) = if error(sn, sd) < error(p1, q1) { (sn, sd) } else { (p1, q1) };
+        Self::checked_from_improper(sign * p, q).unwrap_or(self)
+    }
     pub fn approx_ratio_scaled(&self, digits: usize, step_scale: f32) -> Self {
         let mut new_num = self.num.abs() as f32;
         let mut new_den = self.den as f32;
@@ -142,14 +258,14 @@ impl MixedRational {
         {
             i += 1;
             div *= step_scale;
-            new_num = (self.num / (div as i32)) as f32;
-            new_den = (self.den / (div as u32)) as f32;
-            if new_num as u32 == 0 || new_den as u32 == 0 {
+            new_num = (self.num / (div as i64)) as f32;
+            new_den = (self.den / (div as u64)) as f32;
+            if new_num as u64 == 0 || new_den as u64 == 0 {
                 div /= step_scale;
                 break;
             }
         }
-        MixedRational::new(self.value, self.num / (div as i32), self.den / (div as u32))
+        MixedRational::new(self.value, self.num / (div as i64), self.den / (div as u64))
     }
     pub fn approx_ratio_error_scaled(&self, digits: usize, step_scale: f32) -> (Self, f32) {
         let approx = self.approx_ratio_scaled(digits, step_scale);
@@ -185,7 +301,181 @@ impl Display for MixedRational {
     }
 }
 
-#[allow(clippy::blocks_in_if_conditions)]
+// Closest p/q to n/d with q at most `max`, for n < d, by continued fractions
+fn closest_fraction(n: u128, d: u128, max: u128) -> (u128, u128) {
+    let (mut p0, mut q0, mut p1, mut q1) = (0u128, 1u128, 1u128, 0u128);
+    let (mut rn, mut rd) = (n, d);
+    while rd != 0 {
+        let a = rn / rd;
+        let Some(q2) = a.checked_mul(q1).and_then(|q| q.checked_add(q0)).filter(|q| *q <= max) else {
+            break;
+        };
+        (p0, q0, p1, q1) = (p1, q1, p0 + a * p1, q2);
+        (rn, rd) = (rd, rn % rd);
+    }
+    if q1 == 0 {
+        return (0, 1);
+    }
+    // The best approximation is either the last convergent or this semiconvergent
+    let k = (max - q0) / q1;
+    let (sp, sq) = (p0 + k * p1, q0 + k * q1);
+    let target = n as f64 / d as f64;
+    let error = |p: u128, q: u128| (p as f64 / q as f64 - target).abs();
+    if error(sp, sq) < error(p1, q1) {
+        (sp, sq)
+    } else {
+        (p1, q1)
+    }
+}
+
+pub(crate) const VULGAR_FRACTIONS: &[(char, i64, u64)] = &[
+    ('¼', 1, 4), ('½', 1, 2), ('¾', 3, 4), ('⅐', 1, 7), ('⅑', 1, 9), ('⅒', 1, 10),
+    ('⅓', 1, 3), ('⅔', 2, 3), ('⅕', 1, 5), ('⅖', 2, 5), ('⅗', 3, 5), ('⅘', 4, 5),
+    ('⅙', 1, 6), ('⅚', 5, 6), ('⅛', 1, 8), ('⅜', 3, 8), ('⅝', 5, 8), ('⅞', 7, 8),
+];
+const RANGE_DASHES: &[char] = &['-', '–', '—'];
+const RANGE_WORDS: &[&str] = &["to", "or"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseRationalErrorKind {
+    Empty,
+    InvalidDigit,
+    ZeroDenominator,
+    UnexpectedToken,
+    Overflow,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseRationalError {
+    /// Byte offset of the offending token in the parsed string
+    pub position: usize,
+    pub token: String,
+    pub reason: ParseRationalErrorKind,
+}
+
+impl ParseRationalError {
+    fn new(position: usize, token: &str, reason: ParseRationalErrorKind) -> Self {
+        ParseRationalError {
+            position,
+            token: token.into(),
+            reason,
+        }
+    }
+}
+
+impl Display for ParseRationalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.reason {
+            ParseRationalErrorKind::Empty => "expected a quantity",
+            ParseRationalErrorKind::InvalidDigit => "invalid number",
+            ParseRationalErrorKind::ZeroDenominator => "denominator is zero",
+            ParseRationalErrorKind::UnexpectedToken => "unexpected token",
+            ParseRationalErrorKind::Overflow => "number too large",
+        };
+        write!(f, "{} at {}: \"{}\"", reason, self.position, self.token)
+    }
+}
+
+impl std::error::Error for ParseRationalError {}
+
+impl std::str::FromStr for MixedRational {
+    type Err = ParseRationalError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_quantity(s, 0)
+    }
+}
+
+fn parse_digits<T: std::str::FromStr<Err = std::num::ParseIntError>>(
+    s: &str,
+    pos: usize,
+    token: &str,
+) -> Result<T, ParseRationalError> {
+    use std::num::IntErrorKind;
+    s.parse::<T>().map_err(|e| {
+        let reason = match e.kind() {
+            IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => ParseRationalErrorKind::Overflow,
+            _ => ParseRationalErrorKind::InvalidDigit,
+        };
+        ParseRationalError::new(pos, token, reason)
+    })
+}
+
+/// Parses a single token such as "2", "1/2", "½", "1½" or "2.25"
+fn parse_token(token: &str, pos: usize) -> Result<MixedRational, ParseRationalError> {
+    use ParseRationalErrorKind::*;
+    let overflow = || ParseRationalError::new(pos, token, Overflow);
+    let invalid = || ParseRationalError::new(pos, token, InvalidDigit);
+    if let Some((i, c)) = token.char_indices().find(|(_, c)| VULGAR_FRACTIONS.iter().any(|v| v.0 == *c)) {
+        if i + c.len_utf8() != token.len() {
+            return Err(invalid());
+        }
+        let (_, n, d) = VULGAR_FRACTIONS.iter().find(|v| v.0 == c).unwrap();
+        let whole = if i == 0 { 0 } else { parse_digits::<i64>(&token[..i], pos, token)? };
+        return MixedRational::whole(whole)
+            .checked_add(MixedRational::fract(*n, *d))
+            .ok_or_else(overflow);
+    }
+    if let Some((n, d)) = token.split_once(['/', '⁄']) {
+        let (n, d) = (parse_digits::<i64>(n, pos, token)?, parse_digits::<u64>(d, pos, token)?);
+        if d == 0 {
+            return Err(ParseRationalError::new(pos, token, ZeroDenominator));
+        }
+        return MixedRational::checked_from_improper(n as i128, d as i128).ok_or_else(overflow);
+    }
+    if let Some((w, dec)) = token.split_once('.') {
+        if w.is_empty() && dec.is_empty() {
+            return Err(invalid());
+        }
+        let whole = if w.is_empty() { 0 } else { parse_digits::<i64>(w, pos, token)? };
+        if dec.is_empty() {
+            return Ok(MixedRational::whole(whole));
+        }
+        if !dec.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let den = 10u64.checked_pow(dec.len() as u32).ok_or_else(overflow)?;
+        let num = parse_digits::<i64>(dec, pos, token)?;
+        return MixedRational::whole(whole)
+            .checked_add(MixedRational::fract(num, den))
+            .ok_or_else(overflow);
+    }
+    parse_digits::<i64>(token, pos, token).map(MixedRational::whole)
+}
+
+/// Parses a single quantity, a whole number optionally followed by a fraction
+fn parse_quantity(s: &str, offset: usize) -> Result<MixedRational, ParseRationalError> {
+    use ParseRationalErrorKind::*;
+    let trimmed = s.trim_start();
+    let (negative, body) = match trimmed.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, trimmed),
+    };
+    let start = offset + s.len() - body.len();
+    let mut tokens = body.split_whitespace().map(|t| (start + t.as_ptr() as usize - body.as_ptr() as usize, t));
+    let (first_pos, first) = tokens.next().ok_or_else(|| ParseRationalError::new(offset, s, Empty))?;
+    let mut quantity = parse_token(first, first_pos)?;
+    if let Some((pos, token)) = tokens.next() {
+        // Only "1 1/2" or "1 ½" style pairs are allowed
+        let is_fraction = token.contains(['/', '⁄']) || VULGAR_FRACTIONS.iter().any(|v| token.starts_with(v.0));
+        if quantity.den != 0 || first.contains('.') || !is_fraction {
+            return Err(ParseRationalError::new(pos, token, UnexpectedToken));
+        }
+        let fraction = parse_token(token, pos)?;
+        if fraction.value != 0 || fraction.is_zero() {
+            return Err(ParseRationalError::new(pos, token, UnexpectedToken));
+        }
+        quantity = quantity.checked_add(fraction).ok_or_else(|| ParseRationalError::new(pos, token, Overflow))?;
+    }
+    if let Some((pos, token)) = tokens.next() {
+        return Err(ParseRationalError::new(pos, token, UnexpectedToken));
+    }
+    if negative {
+        quantity = quantity.checked_neg().ok_or_else(|| ParseRationalError::new(start, body, Overflow))?;
+    }
+    Ok(quantity)
+}
+
+#[allow(clippy::blocks_in_conditions)]
 pub fn parse_fract(txt: String) -> Option<MixedRational> {
     if let Some(slash) = txt.find('/') {
         let mut mr = MixedRational::new(0,0,0);
@@ -193,7 +483,7 @@ pub fn parse_fract(txt: String) -> Option<MixedRational> {
         let (left_s, den_s) = txt.split_at(slash);
         // Parse denominator
         let filtered_den = den_s.chars().filter(|x| x.is_numeric()).collect::<String>();
-        if filtered_den.parse::<u32>().map(|d| {mr.den = d;}).is_err() {
+        if filtered_den.parse::<u64>().map(|d| {mr.den = d;}).is_err() {
             return None;
         }
         // of form: "v n/d"
@@ -202,10 +492,10 @@ pub fn parse_fract(txt: String) -> Option<MixedRational> {
             let (value_s, num_s) = left_s.split_at(space);
             // Parse value
             let filtered_value = value_s.chars().filter(|x| x.is_numeric()).collect::<String>();
-            if filtered_value.parse::<i32>().map(|v| {mr.value = v;}).is_ok() {
+            if filtered_value.parse::<i64>().map(|v| {mr.value = v;}).is_ok() {
                 // Parse numerator
                 let filtered_num = num_s.chars().filter(|x| x.is_numeric()).collect::<String>();
-                if filtered_num.parse::<i32>().map(|n| {mr.num = n;}).is_ok() {
+                if filtered_num.parse::<i64>().map(|n| {mr.num = n;}).is_ok() {
                     return Some(mr);
                 }
             }
@@ -213,14 +503,14 @@ pub fn parse_fract(txt: String) -> Option<MixedRational> {
         } else {
             // Parse numerator
             let filtered_num = left_s.chars().filter(|x| x.is_numeric()).collect::<String>();
-            if filtered_num.parse::<i32>().map(|n| {mr.num = n;}).is_ok() {
+            if filtered_num.parse::<i64>().map(|n| {mr.num = n;}).is_ok() {
                 return Some(mr)
             }
         }
     } else {
         // Parse value
         let filtered_value = txt.chars().filter(|x| x.is_numeric()).collect::<String>();
-        if let Ok(value) = filtered_value.parse::<i32>() {
+        if let Ok(value) = filtered_value.parse::<i64>() {
             return Some(MixedRational::whole(value))
         } 
     }
@@ -230,6 +520,12 @@ pub fn parse_fract(txt: String) -> Option<MixedRational> {
 
 impl From<i32> for MixedRational {
     fn from(v: i32) -> Self {
+        Self::whole(v as i64)
+    }
+}
+
+impl From<i64> for MixedRational {
+    fn from(v: i64) -> Self {
         Self::whole(v)
     }
 }
@@ -237,13 +533,7 @@ impl From<i32> for MixedRational {
 #[allow(clippy::from_over_into)]
 impl Into<f32> for MixedRational {
     fn into(self) -> f32 {
-        if self.value != 0 && self.den!= 0 {
-            (self.value as f32)  + (self.num as f32 / self.den as f32)
-        } else if self.den!= 0 {
-            self.num as f32 / self.den as f32
-        } else {
-            self.value as f32
-        }
+        self.to_f64() as f32
     }
 }
 
@@ -261,21 +551,15 @@ impl Into<String> for MixedRational {
 
 impl PartialOrd for MixedRational {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        if self.den != 0 {
-            (self.den as i32 * other.den as i32 * self.num + other.value * self.den as i32 * other.den as i32)
-            .partial_cmp(&(self.den as i32* other.den as i32 * other.num + self.value *self.den as i32 * other.den as i32))
-        } else {
-            self.value.partial_cmp(&other.value)
-        }
+        Some(self.cmp(other))
     }
 }
 impl Ord for MixedRational {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        if self.den != 0 {
-            (self.den as i32 * other.den as i32 * self.num + other.value * self.den as i32 * other.den as i32)
-            .cmp(&(self.den as i32* other.den as i32 * other.num + self.value *self.den as i32 * other.den as i32))
-        } else {
-            self.value.cmp(&other.value)
+        let ((ln, ld), (rn, rd)) = (self.to_improper(), other.to_improper());
+        match (ln.checked_mul(rd), rn.checked_mul(ld)) {
+            (Some(l), Some(r)) => l.cmp(&r),
+            _ => self.to_f64().total_cmp(&other.to_f64()),
         }
     }
 }
@@ -283,61 +567,233 @@ impl Ord for MixedRational {
 impl std::ops::Mul<u32> for MixedRational {
     type Output = MixedRational;
     fn mul(self, rhs: u32) -> Self::Output {
-        MixedRational {
-            value: self.value * (rhs as i32),
-            num: self.num * (rhs as i32),
-            den: self.den,
-        }
-        .simplify()
+        MixedRational::approx_product(self.to_improper(), (rhs as i128, 1))
     }
 }
-// (if self.num != 0 { self.num.abs() } else { 0 })
-// [0, self.num.abs()][self.num != 0 as usize]
+
 impl std::ops::Mul<MixedRational> for MixedRational {
     type Output = MixedRational;
-    #[rustfmt::skip]
     fn mul(self, rhs: MixedRational) -> Self::Output {
-        // Get the sign
-        let sign = [1, -1][(self.value < 0) as usize] * [1, -1][(self.num < 0) as usize]
-                 * [1, -1][(rhs.value < 0) as usize] * [1, -1][(rhs.num < 0) as usize];
-        // Convert to fractions
-        let frac_l = [
-            (self.value.abs(), 1),
-            (self.num.abs() + (self.den as i32 * self.value.abs()), self.den as i32),
-        ][(self.den != 0) as usize];
-        let frac_r = [
-            (rhs.value.abs(), 1),
-            (rhs.num.abs() + (rhs.den as i32 * rhs.value.abs()), rhs.den as i32),
-        ][(rhs.den != 0) as usize];
-        // Return
-        MixedRational::fract(frac_l.0 * frac_r.0 * sign, (frac_r.1 * frac_l.1) as u32)
+        // Too big to be exact is close enough for a recipe
+        self.checked_mul(rhs)
+            .unwrap_or_else(|| MixedRational::approx_product(self.to_improper(), rhs.to_improper()))
     }
 }
 
 impl std::ops::Div<MixedRational> for MixedRational {
     type Output = MixedRational;
     fn div(self, rhs: MixedRational) -> Self::Output {
-        // Get the sign
-        let sign = [1, -1][(self.value < 0) as usize]
-            * [1, -1][(self.num < 0) as usize]
-            * [1, -1][(rhs.value < 0) as usize]
-            * [1, -1][(rhs.num < 0) as usize];
-        // Convert to fractions
-        let frac_l = [
-            (self.value.abs(), 1),
-            (
-                self.num.abs() + (self.den as i32 * self.value.abs()),
-                self.den as i32,
-            ),
-        ][(self.den != 0) as usize];
-        let frac_r = [
-            (rhs.value.abs(), 1),
-            (
-                rhs.num.abs() + (rhs.den as i32 * rhs.value.abs()),
-                rhs.den as i32,
-            ),
-        ][(rhs.den != 0) as usize];
-        // Return
-        MixedRational::fract(frac_l.0 * frac_r.1 * sign, (frac_l.1 * frac_r.0) as u32)
+        // Dividing by zero has always produced zero, recipes without servings rely on this
+        if rhs.is_zero() {
+            return MixedRational::default();
+        }
+        self.checked_div(rhs).unwrap_or_else(|| {
+            let (rn, rd) = rhs.to_improper();
+            MixedRational::approx_product(self.to_improper(), (rd, rn))
+        })
+    }
+}
+
+impl std::ops::Add<MixedRational> for MixedRational {
+    type Output = MixedRational;
+    fn add(self, rhs: MixedRational) -> Self::Output {
+        self.checked_add(rhs)
+            .unwrap_or_else(|| MixedRational::approx_sum(self.to_improper(), rhs.to_improper()))
+    }
+}
+
+impl std::ops::Sub<MixedRational> for MixedRational {
+    type Output = MixedRational;
+    fn sub(self, rhs: MixedRational) -> Self::Output {
+        self.checked_sub(rhs).unwrap_or_else(|| {
+            let (rn, rd) = rhs.to_improper();
+            MixedRational::approx_sum(self.to_improper(), (-rn, rd))
+        })
+    }
+}
+
+impl std::ops::Neg for MixedRational {
+    type Output = MixedRational;
+    fn neg(self) -> Self::Output {
+        self.checked_neg().unwrap_or_else(|| {
+            let (n, d) = self.to_improper();
+            MixedRational::approx_from_improper(-n, d)
+        })
+    }
+}
+
+impl std::ops::AddAssign<MixedRational> for MixedRational {
+    fn add_assign(&mut self, rhs: MixedRational) {
+        *self = *self + rhs;
+    }
+}
+
+impl std::ops::SubAssign<MixedRational> for MixedRational {
+    fn sub_assign(&mut self, rhs: MixedRational) {
+        *self = *self - rhs;
+    }
+}
+
+impl std::iter::Sum for MixedRational {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(MixedRational::default(), |acc, x| acc + x)
+    }
+}
+
+impl<'a> std::iter::Sum<&'a MixedRational> for MixedRational {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.fold(MixedRational::default(), |acc, x| acc + *x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn rational() -> impl Strategy<Value = MixedRational> {
+        (-1_000_000i64..1_000_000, -1_000_000i64..1_000_000, 0u64..1_000_000)
+            .prop_map(|(w, n, d)| if d == 0 { MixedRational::whole(w) } else { MixedRational::new(w, n, d) })
+    }
+
+    fn is_reduced(r: MixedRational) -> bool {
+        if r.den == 0 {
+            return r.num == 0;
+        }
+        let n = r.num.unsigned_abs();
+        n != 0 && n < r.den && MixedRational::gcd(n as i128, r.den as i128) == 1 && (r.value == 0 || r.num > 0)
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        close_within(a, b, a.abs().max(b.abs()))
+    }
+
+    // Floats of nearly opposite values lose their digits when added, so compare to the operands' size
+    fn close_within(a: f32, b: f32, scale: f32) -> bool {
+        (a - b).abs() <= 1e-4 * scale.max(1.)
+    }
+
+    #[test]
+    fn checked_arithmetic_overflows_to_none() {
+        let (max, two) = (MixedRational::whole(i64::MAX), MixedRational::whole(2));
+        assert_eq!(max.checked_add(two), None);
+        assert_eq!((-max).checked_sub(two), None);
+        assert_eq!(max.checked_mul(two), None);
+        assert_eq!(max.checked_div(MixedRational::fract(1, 2)), None);
+        assert_eq!(two.checked_div(MixedRational::default()), None);
+        assert_eq!(MixedRational::fract(1, u64::MAX).checked_mul(MixedRational::fract(1, 3)), None);
+    }
+
+    #[test]
+    fn parses_quantities_and_ranges() {
+        let parse = |s: &str| MixedRational::try_from_string(s);
+        assert_eq!(parse("1 1/2"), Ok((MixedRational::new(1, 1, 2), None)));
+        assert_eq!(parse("1½"), Ok((MixedRational::new(1, 1, 2), None)));
+        assert_eq!(parse("2.25"), Ok((MixedRational::new(2, 1, 4), None)));
+        assert_eq!(parse("2 to 3"), Ok((MixedRational::whole(2), Some(MixedRational::whole(3)))));
+        assert_eq!(parse("2–3"), Ok((MixedRational::whole(2), Some(MixedRational::whole(3)))));
+        assert_eq!(parse("-2"), Ok((MixedRational::whole(-2), None)));
+        assert_eq!(parse("1."), Ok((MixedRational::whole(1), None)));
+        assert_eq!(parse("1/0").unwrap_err().reason, ParseRationalErrorKind::ZeroDenominator);
+        assert_eq!(parse("1 1/2 3").unwrap_err().reason, ParseRationalErrorKind::UnexpectedToken);
+        for typo in ["1--2", "1 - -2", "1-–2"] {
+            let error = parse(typo).unwrap_err();
+            assert_eq!(error.reason, ParseRationalErrorKind::UnexpectedToken, "{}", typo);
+        }
+        assert_eq!(parse("1--2").unwrap_err().position, 2);
+    }
+
+    proptest! {
+        #[test]
+        fn arbitrary_strings_never_panic(s in any::<String>()) {
+            let _ = MixedRational::try_from_string(&s);
+            let _ = s.parse::<MixedRational>();
+            let _ = MixedRational::from_string(s);
+        }
+
+        #[test]
+        fn quantity_like_strings_never_panic(s in "[-–— 0-9/⁄.½¼⅓toTOr]{0,16}") {
+            if let Err(e) = MixedRational::try_from_string(&s) {
+                prop_assert!(e.position <= s.len());
+            }
+            let _ = MixedRational::from_string(s);
+        }
+
+        #[test]
+        fn display_parses_back(a in rational().prop_filter("zero displays as nothing", |a| !a.is_zero())) {
+            prop_assert_eq!(a.to_string().parse::<MixedRational>(), Ok(a));
+        }
+
+        #[test]
+        fn ranges_parse_back(a in rational(), b in rational()) {
+            let (a, b) = (a.abs(), b.abs());
+            prop_assume!(!a.is_zero() && !b.is_zero());
+            prop_assert_eq!(MixedRational::try_from_string(&format!("{}-{}", a, b)), Ok((a, Some(b))));
+            prop_assert_eq!(MixedRational::try_from_string(&format!("{} to {}", a, b)), Ok((a, Some(b))));
+        }
+
+        #[test]
+        fn checked_matches_integer_arithmetic(a in any::<i64>(), b in any::<i64>()) {
+            let (x, y) = (MixedRational::whole(a), MixedRational::whole(b));
+            // i64::MIN has no positive counterpart, so it's never a result
+            let expected = |v: Option<i64>| v.filter(|v| *v != i64::MIN).map(MixedRational::whole);
+            prop_assert_eq!(x.checked_add(y), expected(a.checked_add(b)));
+            prop_assert_eq!(x.checked_sub(y), expected(a.checked_sub(b)));
+            prop_assert_eq!(x.checked_mul(y), expected(a.checked_mul(b)));
+        }
+
+        #[test]
+        fn results_are_reduced(a in rational(), b in rational()) {
+            prop_assert!(is_reduced(a));
+            prop_assert!(is_reduced(a + b));
+            prop_assert!(is_reduced(a - b));
+            prop_assert!(is_reduced(a * b));
+            prop_assert!(is_reduced(-a));
+            if !b.is_zero() {
+                prop_assert!(is_reduced(a / b));
+            }
+        }
+
+        #[test]
+        fn arithmetic_agrees_with_to_float(a in rational(), b in rational()) {
+            let (x, y) = (a.to_float(), b.to_float());
+            let scale = x.abs().max(y.abs());
+            prop_assert!(close_within((a + b).to_float(), x + y, scale));
+            prop_assert!(close_within((a - b).to_float(), x - y, scale));
+            prop_assert!(close((a * b).to_float(), x * y));
+            prop_assert!(close((-a).to_float(), -x));
+            if !b.is_zero() {
+                prop_assert!(close((a / b).to_float(), x / y));
+            }
+            // Rounding to a float never reverses an order
+            if x < y {
+                prop_assert!(a < b);
+            }
+        }
+
+        #[test]
+        fn add_and_sub_round_trip(a in rational(), b in rational()) {
+            prop_assert_eq!(a + b - b, a);
+            prop_assert_eq!(a.checked_add(b).and_then(|s| s.checked_sub(b)), Some(a));
+            if !b.is_zero() {
+                prop_assert_eq!(a * b / b, a);
+            }
+        }
+    }
+
+    #[test]
+    fn operators_approximate_instead_of_overflowing() {
+        let tiny = MixedRational::fract(1, 999999937) * MixedRational::fract(1, 999999929);
+        let product = tiny * MixedRational::fract(1, 999999893);
+        assert!(product.to_float() >= 0. && product.to_float() < 1e-18);
+        let big = MixedRational::whole(i64::MAX);
+        assert_eq!(big + big, MixedRational::whole(i64::MAX));
+        assert_eq!(-big - big, MixedRational::whole(-i64::MAX));
+        assert_eq!(big * 3u32, MixedRational::whole(i64::MAX));
+        assert_eq!(-MixedRational::whole(i64::MIN), MixedRational::whole(i64::MAX));
+        let third = MixedRational::fract(1, 3);
+        let close = MixedRational::fract(i64::MAX - 1, i64::MAX as u64) * third / MixedRational::fract(1, 999999937);
+        assert!((close.to_float() - 999999937. / 3.).abs() < 1.);
+        assert!(big * MixedRational::whole(2) > MixedRational::fract(i64::MAX - 1, i64::MAX as u64));
     }
 }
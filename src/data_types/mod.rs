@@ -1,10 +1,42 @@
+pub mod density;
+pub mod dietary;
+pub mod direction_scaling;
+pub mod food_database;
+pub mod formatting;
+pub mod ingredient_flags;
+pub mod ingredient_groups;
+pub mod ingredient_names;
 pub mod mixed_rational;
+pub mod nested_recipes;
+pub mod nutrition;
 pub mod planning;
 pub mod recipe;
+pub mod shopping;
+pub mod step_links;
+pub mod substitutions;
+pub mod temperature;
+pub mod timers;
+pub mod times;
 pub mod units;
 pub mod web_scraper;
+pub use density::*;
+pub use dietary::*;
+pub use direction_scaling::*;
+pub use food_database::*;
+pub use formatting::*;
+pub use ingredient_flags::*;
+pub use ingredient_groups::*;
+pub use ingredient_names::*;
 pub use mixed_rational::*;
+pub use nested_recipes::*;
+pub use nutrition::*;
 pub use planning::*;
 pub use recipe::*;
+pub use shopping::*;
+pub use step_links::*;
+pub use substitutions::*;
+pub use temperature::*;
+pub use timers::*;
+pub use times::*;
 pub use units::*;
 pub use web_scraper::*;
@@ -0,0 +1,246 @@
+use std::fmt;
+
+use crate::{IngredientCatalog, Measure, MeasureType, MixedRational, ParsedRecipe, RecipeItem, ShoppingList, Unit};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NestedRecipeErrorKind {
+    /// No loaded recipe has the id
+    MissingRecipe,
+    /// Recipes that use each other, ex: "a -> b -> a"
+    Cycle,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NestedRecipeError {
+    pub token: String,
+    pub reason: NestedRecipeErrorKind,
+}
+
+impl fmt::Display for NestedRecipeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.reason {
+            NestedRecipeErrorKind::MissingRecipe => "no recipe with this id",
+            NestedRecipeErrorKind::Cycle => "recipes use each other",
+        };
+        write!(f, "{}: \"{}\"", reason, self.token)
+    }
+}
+
+impl std::error::Error for NestedRecipeError {}
+
+/// A recipe used as an ingredient, at the amount the ingredient asks for
+#[derive(Clone, Debug)]
+pub struct SubRecipe {
+    /// Index into the using recipe's ingredients
+    pub ingredient: usize,
+    pub recipe: ParsedRecipe,
+    pub components: Vec<SubRecipe>,
+}
+
+impl RecipeItem {
+    /// `batches` of `recipe` as an ingredient, ex: 1 pie crust
+    pub fn sub_recipe(recipe: &ParsedRecipe, batches: MixedRational) -> Self {
+        RecipeItem {
+            measure: MeasureType {
+                count: batches,
+                unit: Measure::from_enum(Unit::Batch),
+            },
+            name: recipe.text.title.replace('"', "").to_lowercase(),
+            recipe: Some(recipe.id()),
+            ..Default::default()
+        }
+    }
+    // How many servings of `recipe` this is, a count is of whole recipes, ex: 1 pie crust, and a unit
+    // goes by its serving size
+    fn servings_of(&self, recipe: &ParsedRecipe) -> Option<MixedRational> {
+        let count = self.measure.count;
+        // Not linked yet, `link_sub_recipes` makes it 1 batch
+        if count.is_zero() {
+            return Some(recipe.data.servings);
+        }
+        if matches!(self.measure.unit.unit, Unit::Batch | Unit::Other) {
+            return Some(count * recipe.data.servings);
+        }
+        let size = &recipe.data.nutrition_info;
+        let unit = Measure::new(size.servings_unit.trim().to_lowercase());
+        if size.servings_size.is_zero() || unit.unit == Unit::Other {
+            return None;
+        }
+        Some(self.measure.unit.convert(count, unit)? / size.servings_size)
+    }
+}
+
+fn find_recipe<'a>(collection: &'a [ParsedRecipe], id: &str) -> Option<&'a ParsedRecipe> {
+    collection.iter().find(|r| r.id() == id)
+}
+
+// The ingredients with each sub-recipe replaced by its own
+fn expand(recipe: &ParsedRecipe, subs: &[SubRecipe]) -> Vec<RecipeItem> {
+    recipe
+        .data
+        .ingredients
+        .iter()
+        .enumerate()
+        .flat_map(|(i, item)| match subs.iter().find(|s| s.ingredient == i) {
+            Some(sub) => expand(&sub.recipe, &sub.components),
+            None => vec![item.clone()],
+        })
+        .collect()
+}
+
+impl ParsedRecipe {
+    /// What other recipes refer to this one by, ex: "Pie Crust" is "pie-crust"
+    pub fn id(&self) -> String {
+        self.text
+            .title
+            .to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+    /// Point ingredients named like a recipe in `collection` at it, ex: "1 pie crust", ones without an
+    /// amount use 1 batch so scaling this recipe scales theirs
+    pub fn link_sub_recipes(&mut self, collection: &[ParsedRecipe]) {
+        let catalog = IngredientCatalog::shared();
+        let id = self.id();
+        for item in self.data.ingredients.iter_mut() {
+            if item.recipe.is_none() {
+                let key = catalog.key(&item.name);
+                item.recipe = collection
+                    .iter()
+                    .find(|r| r.id() != id && catalog.key(&r.text.title.replace('"', "")) == key)
+                    .map(|r| r.id());
+            }
+            if item.recipe.is_some() && !item.is_measured() {
+                item.measure = MeasureType {
+                    count: MixedRational::whole(1),
+                    unit: Measure::from_enum(Unit::Batch),
+                };
+            }
+        }
+    }
+    /// The recipes this one uses, scaled to the amounts used and with the ones they use in turn
+    pub fn sub_recipes(&self, collection: &[ParsedRecipe]) -> Result<Vec<SubRecipe>, NestedRecipeError> {
+        self.sub_recipes_within(collection, &mut vec![self.id()])
+    }
+    fn sub_recipes_within(
+        &self,
+        collection: &[ParsedRecipe],
+        path: &mut Vec<String>,
+    ) -> Result<Vec<SubRecipe>, NestedRecipeError> {
+        use NestedRecipeErrorKind::*;
+        let mut subs = Vec::new();
+        for (index, item) in self.data.ingredients.iter().enumerate() {
+            let Some(id) = &item.recipe else {
+                continue;
+            };
+            if let Some(start) = path.iter().position(|p| p == id) {
+                let cycle = path[start..].iter().chain(std::iter::once(id)).cloned().collect::<Vec<_>>();
+                return Err(NestedRecipeError { token: cycle.join(" -> "), reason: Cycle });
+            }
+            let sub = find_recipe(collection, id).ok_or_else(|| NestedRecipeError { token: id.clone(), reason: MissingRecipe })?;
+            // Bought instead when the amount can't be made into servings, ex: cups of a stock recipe
+            // with no serving size
+            let Some(servings) = item.servings_of(sub).filter(|_| !sub.data.servings.is_zero()) else {
+                continue;
+            };
+            let recipe = sub.get_recipe_for_servings(servings);
+            path.push(id.clone());
+            let components = recipe.sub_recipes_within(collection, path)?;
+            path.pop();
+            subs.push(SubRecipe {
+                ingredient: index,
+                recipe,
+                components,
+            });
+        }
+        Ok(subs)
+    }
+    /// Every ingredient down to the ones bought, the sub-recipes' in place of the sub-recipes
+    pub fn expanded_ingredients(&self, collection: &[ParsedRecipe]) -> Result<Vec<RecipeItem>, NestedRecipeError> {
+        Ok(expand(self, &self.sub_recipes(collection)?))
+    }
+}
+
+/// Link the sub-recipes of every recipe in `collection`, once they're all loaded
+pub fn link_nested_recipes(collection: &mut [ParsedRecipe]) {
+    let loaded = collection.to_vec();
+    for recipe in collection.iter_mut() {
+        recipe.link_sub_recipes(&loaded);
+    }
+}
+
+/// Problems with the sub-recipes of every recipe in `collection`, ex: a missing or circular one
+pub fn check_nested_recipes(collection: &[ParsedRecipe]) -> Vec<NestedRecipeError> {
+    collection.iter().filter_map(|r| r.sub_recipes(collection).err()).collect()
+}
+
+impl ShoppingList {
+    /// Adds the recipe with its sub-recipes' ingredients in place of the sub-recipes
+    pub fn add_recipe_expanded(&mut self, recipe: &ParsedRecipe, collection: &[ParsedRecipe]) -> Result<(), NestedRecipeError> {
+        for item in recipe.expanded_ingredients(collection)? {
+            self.add(item);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recipe(title: &str, servings: i64, lines: &[&str]) -> ParsedRecipe {
+        let mut recipe = ParsedRecipe::default();
+        recipe.text.title = title.into();
+        recipe.data.servings = MixedRational::whole(servings);
+        recipe.data.ingredients = lines.iter().filter_map(|l| RecipeItem::parse(l, None)).collect();
+        recipe
+    }
+
+    #[test]
+    fn unmeasured_sub_recipe_is_one_batch_and_scales() {
+        let mut collection = vec![
+            recipe("Apple Pie", 8, &["pie crust", "6 apples"]),
+            recipe("Pie Crust", 8, &["2 cups flour"]),
+        ];
+        link_nested_recipes(&mut collection);
+        let crust = &collection[0].data.ingredients[0];
+        assert_eq!(crust.recipe.as_deref(), Some("pie-crust"));
+        assert_eq!((crust.measure.count, crust.measure.unit.unit), (MixedRational::whole(1), Unit::Batch));
+
+        let doubled = collection[0].get_recipe_for_servings(MixedRational::whole(16));
+        let flour = doubled.expanded_ingredients(&collection).unwrap();
+        let flour = flour.iter().find(|i| i.name == "flour").unwrap();
+        assert_eq!(flour.measure.count, MixedRational::whole(4));
+    }
+
+    #[test]
+    fn counted_sub_recipe_is_whole_recipes() {
+        let mut collection = vec![
+            recipe("Apple Pie", 8, &["1 pie crust", "6 apples"]),
+            recipe("Pie Crust", 8, &["2 cups flour"]),
+        ];
+        link_nested_recipes(&mut collection);
+        let expanded = collection[0].expanded_ingredients(&collection).unwrap();
+        let flour = expanded.iter().find(|i| i.name == "flour").unwrap();
+        assert_eq!((flour.measure.count, flour.measure.unit.unit), (MixedRational::whole(2), Unit::Cup));
+    }
+
+    #[test]
+    fn unknown_amount_is_bought_as_is() {
+        let mut collection = vec![
+            recipe("Soup", 4, &["2 cups chicken stock", "1 onion"]),
+            recipe("Chicken Stock", 8, &["1 chicken"]),
+        ];
+        link_nested_recipes(&mut collection);
+        assert_eq!(collection[0].data.ingredients[0].recipe.as_deref(), Some("chicken-stock"));
+        assert!(check_nested_recipes(&collection).is_empty());
+        let expanded = collection[0].expanded_ingredients(&collection).unwrap();
+        assert_eq!(expanded.len(), 2);
+        assert_eq!(expanded[0].name, "chicken stock");
+        let mut list = ShoppingList::default();
+        list.add_recipe_expanded(&collection[0], &collection).unwrap();
+        assert_eq!(list.items.len(), 2);
+    }
+}
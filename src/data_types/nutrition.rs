@@ -0,0 +1,435 @@
+use serde::de::Visitor;
+use std::collections::HashMap;
+
+use crate::{MixedRational, NutritionInfo, QuantityFormat, RecipeData};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Nutrient {
+    Energy,
+    Fat,
+    SaturatedFat,
+    TransFat,
+    UnsaturatedFat,
+    Cholesterol,
+    Sodium,
+    Carbohydrate,
+    Fiber,
+    Sugar,
+    AddedSugar,
+    Protein,
+    VitaminD,
+    Calcium,
+    Iron,
+    Potassium,
+}
+
+impl Nutrient {
+    /// In the order they go on a label
+    pub const ALL: [Nutrient; 16] = [
+        Nutrient::Energy,
+        Nutrient::Fat,
+        Nutrient::SaturatedFat,
+        Nutrient::TransFat,
+        Nutrient::UnsaturatedFat,
+        Nutrient::Cholesterol,
+        Nutrient::Sodium,
+        Nutrient::Carbohydrate,
+        Nutrient::Fiber,
+        Nutrient::Sugar,
+        Nutrient::AddedSugar,
+        Nutrient::Protein,
+        Nutrient::VitaminD,
+        Nutrient::Calcium,
+        Nutrient::Iron,
+        Nutrient::Potassium,
+    ];
+    /// Its key in schema.org `NutritionInformation`, if it has one
+    pub fn schema_key(&self) -> Option<&'static str> {
+        match self {
+            Nutrient::Energy => Some("calories"),
+            Nutrient::Fat => Some("fatContent"),
+            Nutrient::SaturatedFat => Some("saturatedFatContent"),
+            Nutrient::TransFat => Some("transFatContent"),
+            Nutrient::UnsaturatedFat => Some("unsaturatedFatContent"),
+            Nutrient::Cholesterol => Some("cholesterolContent"),
+            Nutrient::Sodium => Some("sodiumContent"),
+            Nutrient::Carbohydrate => Some("carbohydrateContent"),
+            Nutrient::Fiber => Some("fiberContent"),
+            Nutrient::Sugar => Some("sugarContent"),
+            Nutrient::Protein => Some("proteinContent"),
+            _ => None,
+        }
+    }
+    pub fn from_schema_key(key: &str) -> Option<Self> {
+        Nutrient::ALL.into_iter().find(|n| n.schema_key() == Some(key))
+    }
+    pub fn label(&self) -> &'static str {
+        match self {
+            Nutrient::Energy => "Calories",
+            Nutrient::Fat => "Total Fat",
+            Nutrient::SaturatedFat => "Saturated Fat",
+            Nutrient::TransFat => "Trans Fat",
+            Nutrient::UnsaturatedFat => "Unsaturated Fat",
+            Nutrient::Cholesterol => "Cholesterol",
+            Nutrient::Sodium => "Sodium",
+            Nutrient::Carbohydrate => "Total Carbohydrate",
+            Nutrient::Fiber => "Dietary Fiber",
+            Nutrient::Sugar => "Total Sugars",
+            Nutrient::AddedSugar => "Added Sugars",
+            Nutrient::Protein => "Protein",
+            Nutrient::VitaminD => "Vitamin D",
+            Nutrient::Calcium => "Calcium",
+            Nutrient::Iron => "Iron",
+            Nutrient::Potassium => "Potassium",
+        }
+    }
+    /// How far it's indented under another nutrient on a label
+    pub fn depth(&self) -> usize {
+        match self {
+            Nutrient::SaturatedFat | Nutrient::TransFat | Nutrient::UnsaturatedFat => 1,
+            Nutrient::Fiber | Nutrient::Sugar => 1,
+            Nutrient::AddedSugar => 2,
+            _ => 0,
+        }
+    }
+    /// The unit sources use when they don't say
+    pub fn default_unit(&self) -> NutrientUnit {
+        match self {
+            Nutrient::Energy => NutrientUnit::Kilocalorie,
+            Nutrient::Cholesterol | Nutrient::Sodium | Nutrient::Calcium | Nutrient::Iron | Nutrient::Potassium => {
+                NutrientUnit::Milligram
+            }
+            Nutrient::VitaminD => NutrientUnit::Microgram,
+            _ => NutrientUnit::Gram,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum NutrientUnit {
+    Kilocalorie,
+    Kilojoule,
+    Gram,
+    Milligram,
+    Microgram,
+}
+
+impl NutrientUnit {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().trim_end_matches('.').to_lowercase().as_str() {
+            "kcal" | "cal" | "calorie" | "calories" | "kilocalorie" | "kilocalories" => Some(NutrientUnit::Kilocalorie),
+            "kj" | "kilojoule" | "kilojoules" => Some(NutrientUnit::Kilojoule),
+            "g" | "gram" | "grams" => Some(NutrientUnit::Gram),
+            "mg" | "milligram" | "milligrams" => Some(NutrientUnit::Milligram),
+            "µg" | "μg" | "ug" | "mcg" | "microgram" | "micrograms" => Some(NutrientUnit::Microgram),
+            _ => None,
+        }
+    }
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            NutrientUnit::Kilocalorie => "kcal",
+            NutrientUnit::Kilojoule => "kJ",
+            NutrientUnit::Gram => "g",
+            NutrientUnit::Milligram => "mg",
+            NutrientUnit::Microgram => "µg",
+        }
+    }
+    pub fn is_energy(&self) -> bool {
+        matches!(self, NutrientUnit::Kilocalorie | NutrientUnit::Kilojoule)
+    }
+    // Size in kilocalories for energy and micrograms for mass, 1 kcal is 4.184 kJ
+    fn base(&self) -> MixedRational {
+        match self {
+            NutrientUnit::Kilocalorie => MixedRational::whole(1),
+            NutrientUnit::Kilojoule => MixedRational::fract(125, 523),
+            NutrientUnit::Gram => MixedRational::whole(1000000),
+            NutrientUnit::Milligram => MixedRational::whole(1000),
+            NutrientUnit::Microgram => MixedRational::whole(1),
+        }
+    }
+}
+
+/// An amount of a nutrient, ex: 12.5 g of fat
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct NutrientAmount {
+    pub nutrient: Nutrient,
+    pub amount: MixedRational,
+    pub unit: NutrientUnit,
+}
+
+impl NutrientAmount {
+    pub fn new(nutrient: Nutrient, amount: MixedRational, unit: NutrientUnit) -> Self {
+        NutrientAmount { nutrient, amount, unit }
+    }
+    /// None when going between energy and mass
+    pub fn convert(&self, unit: NutrientUnit) -> Option<Self> {
+        if unit.is_energy() != self.unit.is_energy() {
+            return None;
+        }
+        Some(NutrientAmount {
+            amount: self.amount * self.unit.base() / unit.base(),
+            unit,
+            ..*self
+        })
+    }
+}
+
+impl std::fmt::Display for NutrientAmount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}{}", self.nutrient.label(), self.amount, self.unit.symbol())
+    }
+}
+
+pub struct NutrientAmountVisitor;
+impl<'de> Visitor<'de> for NutrientAmountVisitor {
+    type Value = Option<NutrientAmount>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "A nutrient with an amount and unit")
+    }
+
+    // Recipes saved before nutrients were typed store them like ingredients named by schema.org key
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        let (mut nutrient, mut amount, mut unit) = (None, None, None);
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "nutrient" => nutrient = Some(map.next_value::<Nutrient>()?),
+                "name" => nutrient = Nutrient::from_schema_key(&map.next_value::<String>()?),
+                "amount" | "count" => amount = Some(map.next_value::<MixedRational>()?),
+                "unit" => unit = Some(map.next_value::<String>()?),
+                _ => {
+                    map.next_value::<serde::de::IgnoredAny>()?;
+                }
+            }
+        }
+        let Some(nutrient) = nutrient else {
+            return Ok(None);
+        };
+        let unit = unit
+            .and_then(|u| {
+                NutrientUnit::parse(&u).or_else(|| serde_json::from_value(serde_json::Value::String(u)).ok())
+            })
+            .unwrap_or(nutrient.default_unit());
+        Ok(Some(NutrientAmount {
+            nutrient,
+            amount: amount.unwrap_or_default(),
+            unit,
+        }))
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for NutrientAmount {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_map(NutrientAmountVisitor)?
+            .ok_or_else(|| serde::de::Error::custom("unknown nutrient"))
+    }
+}
+
+// Nutrients this version doesn't know about are dropped instead of failing the whole recipe
+struct LenientNutrient(Option<NutrientAmount>);
+
+impl<'de> serde::Deserialize<'de> for LenientNutrient {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_map(NutrientAmountVisitor).map(LenientNutrient)
+    }
+}
+
+pub(crate) fn deserialize_nutrients<'de, D>(deserializer: D) -> Result<Vec<NutrientAmount>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let nutrients = <Vec<LenientNutrient> as serde::Deserialize>::deserialize(deserializer)?;
+    Ok(nutrients.into_iter().filter_map(|n| n.0).collect())
+}
+
+/// Daily intakes that % daily values are measured against
+#[derive(Clone, Debug)]
+pub struct ReferenceIntakes {
+    pub intakes: HashMap<Nutrient, NutrientAmount>,
+}
+
+impl ReferenceIntakes {
+    fn from_table(table: &[(Nutrient, i64, NutrientUnit)]) -> Self {
+        ReferenceIntakes {
+            intakes: table
+                .iter()
+                .map(|(n, amount, unit)| (*n, NutrientAmount::new(*n, MixedRational::whole(*amount), *unit)))
+                .collect(),
+        }
+    }
+    /// US daily values for adults on a 2000 calorie diet
+    #[rustfmt::skip]
+    pub fn fda() -> Self {
+        use Nutrient::*;
+        use NutrientUnit::*;
+        Self::from_table(&[
+            (Energy, 2000, Kilocalorie), (Fat, 78, Gram), (SaturatedFat, 20, Gram), (Cholesterol, 300, Milligram),
+            (Sodium, 2300, Milligram), (Carbohydrate, 275, Gram), (Fiber, 28, Gram), (AddedSugar, 50, Gram),
+            (Protein, 50, Gram), (VitaminD, 20, Microgram), (Calcium, 1300, Milligram), (Iron, 18, Milligram),
+            (Potassium, 4700, Milligram),
+        ])
+    }
+    /// EU reference intakes for an average adult, the 6 g of salt is given as sodium
+    #[rustfmt::skip]
+    pub fn eu() -> Self {
+        use Nutrient::*;
+        use NutrientUnit::*;
+        Self::from_table(&[
+            (Energy, 8400, Kilojoule), (Fat, 70, Gram), (SaturatedFat, 20, Gram), (Carbohydrate, 260, Gram),
+            (Sugar, 90, Gram), (Protein, 50, Gram), (Sodium, 2400, Milligram),
+        ])
+    }
+    pub fn set(&mut self, intake: NutrientAmount) {
+        self.intakes.insert(intake.nutrient, intake);
+    }
+    /// Percent of the daily intake, None without a reference for it
+    pub fn daily_value(&self, amount: &NutrientAmount) -> Option<MixedRational> {
+        let reference = self.intakes.get(&amount.nutrient)?;
+        let amount = amount.convert(reference.unit)?;
+        if reference.amount.is_zero() {
+            return None;
+        }
+        Some(amount.amount / reference.amount * MixedRational::whole(100))
+    }
+}
+
+impl Default for ReferenceIntakes {
+    fn default() -> Self {
+        Self::fda()
+    }
+}
+
+/// Whether nutrients are for one serving or everything the recipe makes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum NutritionBasis {
+    /// What schema.org nutrition means
+    #[default]
+    PerServing,
+    PerRecipe,
+}
+
+/// Splits a schema.org `servingSize` into an amount and what it's measured in, ex: "1 cup" or "250 g"
+pub fn parse_serving_size(size: &str) -> Option<(MixedRational, String)> {
+    let size = size.trim();
+    let split = size.find(|c: char| c.is_alphabetic()).unwrap_or(size.len());
+    let (amount, unit) = size.split_at(split);
+    let amount = if amount.trim().is_empty() {
+        MixedRational::whole(1)
+    } else {
+        MixedRational::try_from_string(amount.trim()).ok()?.0
+    };
+    Some((amount, unit.trim().into()))
+}
+
+impl NutritionInfo {
+    // Nutrient amounts are kept in the units the source used, so only the counts change
+    fn scale_nutrients(&self, rhs: MixedRational) -> Self {
+        let mut new = self.clone();
+        for nutrient in new.nutrients.iter_mut() {
+            nutrient.amount = nutrient.amount * rhs;
+        }
+        new
+    }
+    /// The nutrients of one of `servings`
+    pub fn per_serving(&self, servings: MixedRational) -> Self {
+        if self.basis == NutritionBasis::PerServing || servings.is_zero() {
+            return self.clone();
+        }
+        NutritionInfo {
+            basis: NutritionBasis::PerServing,
+            ..self.scale_nutrients(MixedRational::whole(1) / servings)
+        }
+    }
+    /// The nutrients of all `servings` together
+    pub fn per_recipe(&self, servings: MixedRational) -> Self {
+        if self.basis == NutritionBasis::PerRecipe {
+            return self.clone();
+        }
+        NutritionInfo {
+            basis: NutritionBasis::PerRecipe,
+            ..self.scale_nutrients(servings)
+        }
+    }
+}
+
+/// Scaling a recipe by `rhs`, a serving stays the same size so only per recipe totals change
+impl std::ops::Mul<MixedRational> for NutritionInfo {
+    type Output = NutritionInfo;
+    fn mul(self, rhs: MixedRational) -> Self::Output {
+        match self.basis {
+            NutritionBasis::PerServing => self,
+            NutritionBasis::PerRecipe => self.scale_nutrients(rhs),
+        }
+    }
+}
+
+impl RecipeData {
+    /// Nutrition for the whole batch
+    pub fn batch_nutrition(&self) -> NutritionInfo {
+        self.nutrition_info.per_recipe(self.servings)
+    }
+    /// Nutrition for a single serving
+    pub fn serving_nutrition(&self) -> NutritionInfo {
+        self.nutrition_info.per_serving(self.servings)
+    }
+}
+
+impl NutritionInfo {
+    pub fn get(&self, nutrient: Nutrient) -> Option<&NutrientAmount> {
+        self.nutrients.iter().find(|n| n.nutrient == nutrient)
+    }
+    /// A nutrition facts panel with % daily values against `intakes`
+    pub fn label(&self, intakes: &ReferenceIntakes) -> String {
+        let fmt = QuantityFormat::decimal(1);
+        let width = 34;
+        let mut label = String::from("Nutrition Facts\n");
+        if !self.servings_size.is_zero() {
+            label.push_str(&format!(
+                "Serving size {} {}\n",
+                fmt.quantity(self.servings_size),
+                self.servings_unit
+            ));
+        }
+        if self.basis == NutritionBasis::PerRecipe {
+            label.push_str("Amounts for the whole recipe\n");
+        }
+        label.push_str(&format!("{}\n", "=".repeat(width)));
+        if let Some(energy) = self.get(Nutrient::Energy) {
+            let kcal = energy.convert(NutrientUnit::Kilocalorie).unwrap_or(*energy);
+            label.push_str(&format!("Calories{:>w$}\n", fmt.quantity(kcal.amount), w = width - 8));
+            label.push_str(&format!("{}\n", "-".repeat(width)));
+        }
+        label.push_str(&format!("{:>w$}\n", "% Daily Value*", w = width));
+        for nutrient in Nutrient::ALL.iter().filter(|n| **n != Nutrient::Energy) {
+            let Some(amount) = self.get(*nutrient) else {
+                continue;
+            };
+            let name = format!(
+                "{}{} {}{}",
+                "  ".repeat(nutrient.depth()),
+                nutrient.label(),
+                fmt.quantity(amount.amount),
+                amount.unit.symbol()
+            );
+            let daily = intakes
+                .daily_value(amount)
+                .map(|p| format!("{}%", QuantityFormat::decimal(0).quantity(p)))
+                .unwrap_or_default();
+            let line = format!("{}{:>w$}", name, daily, w = width.saturating_sub(name.chars().count()));
+            label.push_str(&format!("{}\n", line.trim_end()));
+        }
+        label.push_str(&format!("{}\n", "-".repeat(width)));
+        label.push_str("* Percent of daily reference intakes\n");
+        label
+    }
+}
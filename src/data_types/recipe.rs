@@ -1,7 +1,7 @@
 use serde::{de::IntoDeserializer, ser::SerializeStruct};
 use serde_json::Value;
 
-use crate::{mixed_rational::MixedRational, Measure, MeasureType, Unit};
+use crate::{mixed_rational::MixedRational, round_to_unit, Measure, MeasureType, QuantityFormat, RecipeTimes, Timer, Unit, UnitType, find_timers, parse_serving_size, deserialize_nutrients, NutrientAmount, NutrientUnit, Nutrient, NutritionBasis, DietaryProfile, DietaryRules, IngredientCatalog, IngredientFlag, extract_flags, IngredientGroup, group_heading};
 use std::fmt;
 
 fn none<T>(s: &Option<T>) -> bool {
@@ -22,12 +22,128 @@ pub struct RecipeItem {
     #[serde(skip_serializing_if = "none", default = "default_option")]
     pub note: Option<String>,
     pub plural: bool,
+    /// How much each container holds, ex: the 14.5 oz in "1 (14.5 oz) can"
+    #[serde(skip_serializing_if = "none", default = "default_option")]
+    pub container_size: Option<MeasureType>,
+    /// What can be used instead, ex: the margarine in "1 cup butter or margarine"
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub alternatives: Vec<Alternative>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub flags: Vec<IngredientFlag>,
+    /// Id of the recipe this is made by, ex: "pie-crust", the amount is servings of it
+    #[serde(skip_serializing_if = "none", default = "default_option")]
+    pub recipe: Option<String>,
+}
+
+/// Another ingredient a recipe allows, with no measure it's used in the same amount
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Alternative {
+    #[serde(serialize_with = "crate::proper_string_serialize")]
+    pub name: String,
+    #[serde(skip_serializing_if = "none", default = "default_option")]
+    pub measure: Option<MeasureType>,
 }
 
 #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct DirectionSection {
     pub name: String,
-    pub sections: Vec<String>,
+    pub sections: Vec<DirectionStep>,
+}
+
+/// A single instruction and what could be read out of it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirectionStep {
+    pub text: String,
+    pub timers: Vec<Timer>,
+    /// Indices into the recipe's ingredients
+    pub ingredients: Vec<usize>,
+}
+
+impl DirectionStep {
+    pub fn new(text: String) -> Self {
+        DirectionStep {
+            timers: find_timers(&text),
+            text,
+            ingredients: Vec::new(),
+        }
+    }
+}
+
+impl From<String> for DirectionStep {
+    fn from(text: String) -> Self {
+        DirectionStep::new(text)
+    }
+}
+
+impl std::fmt::Display for DirectionStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.display_text())
+    }
+}
+
+// Steps with nothing read out of them are saved as plain text, like they always were
+impl serde::Serialize for DirectionStep {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        if self.timers.is_empty() && self.ingredients.is_empty() {
+            return serializer.serialize_str(&self.text);
+        }
+        let mut st = serializer.serialize_struct("DirectionStep", 3)?;
+        st.serialize_field("text", &self.text)?;
+        st.serialize_field("timers", &self.timers)?;
+        st.serialize_field("ingredients", &self.ingredients)?;
+        st.end()
+    }
+}
+
+pub struct DirectionStepVisitor;
+impl<'de> serde::de::Visitor<'de> for DirectionStepVisitor {
+    type Value = DirectionStep;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "A direction, either as text or a step with its text")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(DirectionStep::new(v.into()))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        let (mut text, mut timers, mut ingredients) = (None, None, Vec::new());
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "text" => text = Some(map.next_value::<String>()?),
+                "timers" => timers = Some(map.next_value::<Vec<Timer>>()?),
+                "ingredients" => ingredients = map.next_value::<Vec<usize>>()?,
+                _ => {
+                    map.next_value::<serde::de::IgnoredAny>()?;
+                }
+            }
+        }
+        let mut step = DirectionStep::new(text.ok_or_else(|| serde::de::Error::missing_field("text"))?);
+        if let Some(timers) = timers {
+            step.timers = timers;
+        }
+        step.ingredients = ingredients;
+        Ok(step)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for DirectionStep {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(DirectionStepVisitor)
+    }
 }
 
 #[derive(Clone, Default, Debug, serde::Serialize, serde::Deserialize)]
@@ -35,7 +151,10 @@ pub struct NutritionInfo {
     pub servings_size: MixedRational,
     #[serde(serialize_with = "crate::proper_string_serialize")]
     pub servings_unit: String,
-    pub nutrients: Vec<RecipeItem>,
+    #[serde(deserialize_with = "deserialize_nutrients")]
+    pub nutrients: Vec<NutrientAmount>,
+    #[serde(default)]
+    pub basis: NutritionBasis,
 }
 
 #[derive(Clone, Default, Debug, serde::Serialize, serde::Deserialize)]
@@ -44,6 +163,8 @@ pub struct RecipeData {
     pub original_servings: Option<MixedRational>,
     pub servings: MixedRational,
     pub ingredients: Vec<RecipeItem>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub ingredient_groups: Vec<IngredientGroup>,
     pub directions: Vec<DirectionSection>,
     pub nutrition_info: NutritionInfo,
 }
@@ -51,15 +172,23 @@ pub struct RecipeData {
 #[derive(Clone, Default, Debug, serde::Deserialize)]
 pub struct RecipeText {
     pub title: String,
-    pub prep_time: String,
+    /// Older recipe files have a single `prep_time` string, read as the total
+    #[serde(default, alias = "prep_time")]
+    pub times: RecipeTimes,
     pub author_name: String,
     pub origin: String,
     pub description: String,
+    /// Which regional units the recipe was written in, ex: 20ml Australian tablespoons
+    #[serde(default)]
+    pub region: Option<UnitType>,
 }
 
 #[derive(Clone, Default, Debug, serde::Serialize, serde::Deserialize)]
 pub struct ParsedRecipe {
     pub keywords: Vec<String>,
+    /// Worked out from the ingredients, unlike `keywords` which come from the source
+    #[serde(default)]
+    pub dietary: DietaryProfile,
     #[serde(flatten)]
     pub text: RecipeText,
     #[serde(flatten)]
@@ -68,7 +197,79 @@ pub struct ParsedRecipe {
 
 impl RecipeItem {
     pub fn memory_size(&self) -> usize {
-        std::mem::size_of_val(&self.name) + self.measure.memory_size()
+        std::mem::size_of_val(&self.name)
+            + self.measure.memory_size()
+            + self
+                .alternatives
+                .iter()
+                .map(|a| std::mem::size_of_val(&a.name) + a.measure.map_or(0, |m| m.memory_size()))
+                .sum::<usize>()
+    }
+    pub fn to_unit_system(&self, system: UnitType) -> Self {
+        let measure = self.measure.to_unit_system(system);
+        // Keep the upper bound of a range in the same unit as the lower bound
+        let measure_b = self.measure_b.map(|b| {
+            b.unit.convert(b.count, measure.unit).map_or(b, |count| MeasureType {
+                count: if b.unit.unit == measure.unit.unit { count } else { round_to_unit(count, measure.unit.unit) },
+                unit: measure.unit,
+            })
+        });
+        RecipeItem {
+            measure,
+            measure_b,
+            container_size: self.container_size.map(|s| s.to_unit_system(system)),
+            alternatives: self
+                .alternatives
+                .iter()
+                .map(|a| Alternative {
+                    name: a.name.clone(),
+                    measure: a.measure.map(|m| m.to_unit_system(system)),
+                })
+                .collect(),
+            ..self.clone()
+        }
+    }
+    /// Everything in the containers together, ex: 2 (14.5 oz) cans is 29 oz
+    pub fn contents(&self) -> Option<MeasureType> {
+        self.container_size.map(|size| MeasureType {
+            count: size.count * self.measure.count,
+            unit: size.unit,
+        })
+    }
+    /// This item made with alternative `index` instead, the main ingredient becomes an alternative
+    pub fn with_alternative(&self, index: usize) -> Option<Self> {
+        let chosen = self.alternatives.get(index)?;
+        let replaced = Alternative {
+            name: self.name.trim().to_string(),
+            measure: chosen.measure.map(|_| self.contents().unwrap_or(self.measure)),
+        };
+        let mut alternatives = self.alternatives.clone();
+        alternatives[index] = replaced;
+        let mut item = RecipeItem {
+            name: chosen.name.clone(),
+            alternatives,
+            ..self.clone()
+        };
+        if let Some(measure) = chosen.measure {
+            item.measure = measure;
+            item.measure_b = None;
+            item.container_size = None;
+        }
+        Some(item)
+    }
+    /// Use the first of `preferred` the recipe allows, ex: margarine when there's no butter
+    pub fn prefer<S: AsRef<str>>(&self, preferred: &[S]) -> Self {
+        let catalog = IngredientCatalog::shared();
+        let main = catalog.key(&self.name);
+        for key in preferred.iter().map(|p| catalog.key(p.as_ref())) {
+            if key == main {
+                break;
+            }
+            if let Some(index) = self.alternatives.iter().position(|a| catalog.key(&a.name) == key) {
+                return self.with_alternative(index).unwrap_or_else(|| self.clone());
+            }
+        }
+        self.clone()
     }
 }
 
@@ -76,7 +277,7 @@ impl NutritionInfo {
     pub fn memory_size(&self) -> usize {
         self.nutrients
             .iter()
-            .map(|x| x.memory_size())
+            .map(std::mem::size_of_val)
             .sum::<usize>()
             + self.servings_size.memory_size()
             + std::mem::size_of_val(&self.servings_unit)
@@ -89,6 +290,7 @@ impl RecipeData {
         self.servings.memory_size()
             + self.nutrition_info.memory_size()
             + self.ingredients.iter().map(|x| x.memory_size()).sum::<usize>()
+            + self.ingredient_groups.iter().map(|g| std::mem::size_of_val(&g.name) + std::mem::size_of_val(&g.ingredients[..])).sum::<usize>()
             + self.directions.iter().map(std::mem::size_of_val).sum::<usize>()
     }
     pub fn scale_servings(&self, target_servings: MixedRational) -> Self {
@@ -100,7 +302,7 @@ impl RecipeData {
 impl RecipeText {
     pub fn memory_size(&self) -> usize {
         std::mem::size_of_val(&self.title)
-            + std::mem::size_of_val(&self.prep_time)
+            + std::mem::size_of_val(&self.times)
             + std::mem::size_of_val(&self.author_name)
             + std::mem::size_of_val(&self.origin)
             + std::mem::size_of_val(&self.description)
@@ -114,11 +316,32 @@ impl ParsedRecipe {
             + self.data.memory_size()
             + self.text.memory_size()
     }
+    /// Convert all ingredients into `system`, count only items are left untouched
+    pub fn to_unit_system(&self, system: UnitType) -> Self {
+        let mut new = self.clone();
+        for item in new.data.ingredients.iter_mut() {
+            *item = item.to_unit_system(system);
+        }
+        new
+    }
+    /// Declare which region the recipe is from, generic units like "cup" become the regional version
+    pub fn set_region(&self, region: UnitType) -> Self {
+        let mut new = self.clone();
+        new.text.region = Some(region);
+        for item in new.data.ingredients.iter_mut() {
+            let alternatives = item.alternatives.iter_mut().filter_map(|a| a.measure.as_mut());
+            for measure in std::iter::once(&mut item.measure).chain(item.measure_b.as_mut()).chain(alternatives) {
+                measure.unit = measure.unit.in_region(region);
+            }
+        }
+        new
+    }
     pub fn get_recipe_for_servings(&self, target_servings: MixedRational) -> Self {
         ParsedRecipe {
             data: self.data.scale_servings(target_servings),
             text: self.text.clone(),
             keywords: self.keywords.clone(),
+            dietary: self.dietary.clone(),
         }
     }
     pub fn from_path<P>(path: P) -> Result<Self, Box<dyn std::error::Error>>
@@ -127,9 +350,15 @@ impl ParsedRecipe {
     {
         let hm = std::fs::read_to_string(path)?;
         let json: Value = serde_json::from_str(&hm)?;
-        Ok(ParsedRecipe::deserialize_json_string(
-            json.into_deserializer(),
-        )?)
+        let mut recipe = ParsedRecipe::deserialize_json_string(json.into_deserializer())?;
+        // Recipes saved before steps were linked to their ingredients
+        if !recipe.data.is_linked() {
+            recipe.data.link_ingredients();
+        }
+        if recipe.dietary.is_empty() {
+            recipe.analyze_dietary(DietaryRules::shared());
+        }
+        Ok(recipe)
     }
     fn deserialize_json_string<'de, D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -142,81 +371,13 @@ impl ParsedRecipe {
 
 impl std::fmt::Display for RecipeItem {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let note = if let Some(n) = self.note.clone() {
-            format!(" ({})", n)
-        } else {
-            "".into()
-        };
-        let unit = if self.plural {
-            format!("{}s", self.measure.unit)
-        } else {
-            self.measure.unit.names[0].to_string()
-        };
-        if let Some(m) = self.measure_b {
-            write!(
-                f,
-                "{}-{} {} {}{}",
-                self.measure.count, m.count, unit, self.name, note
-            )
-        } else {
-            write!(f, "{} {} {}{}", self.measure.count, unit, self.name, note)
-        }
+        write!(f, "{}", self.format(&QuantityFormat::default()))
     }
 }
 
 impl std::fmt::Display for ParsedRecipe {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut string = String::new();
-        string.push_str("\n\n\t");
-        string.push_str(&self.text.title);
-        string.push_str("\n\t");
-        string.push_str("By: ");
-        string.push_str(&self.text.author_name);
-        string.push_str("\n\n");
-
-        string.push_str("From: ");
-        string.push_str(&self.text.origin);
-        string.push_str("\n\n");
-
-        string.push_str(&self.text.description);
-        string.push_str("\n\n");
-
-        string.push_str("Estimated Prep Time: ");
-        string.push_str(&self.text.prep_time);
-        string.push('\n');
-
-        string.push_str("Servings: ");
-        string.push_str(&self.data.servings.to_string());
-        string.push_str("\n\n");
-
-        string.push_str("Ingredients:\n");
-        let c = self.data.ingredients.len().to_string().len() + 2;
-        for (i, ingredient) in self.data.ingredients.iter().enumerate() {
-            string.push_str(&format!("{: >n$}) {}\n", i, ingredient, n = c));
-        }
-
-        string.push('\n');
-        string.push_str("Directions: \n");
-        let c = self.data.directions.len().to_string().len() + 2;
-        for section in self.data.directions.iter() {
-            string.push_str(&format!("{} {}\n", " ".repeat(c - 2), section.name));
-            for (i, direction) in section.sections.iter().enumerate() {
-                string.push_str(&format!("{: >n$}) {}\n", i, direction, n = c * 2));
-            }
-        }
-
-        string.push_str("\nServing Size: ");
-        string.push_str(&self.data.nutrition_info.servings_size.to_string());
-        string.push(' ');
-        string.push_str(&self.data.nutrition_info.servings_unit.to_string());
-        string.push_str("\n\n");
-
-        string.push_str("Nutrition Info:\n");
-        for nutrient in self.data.nutrition_info.nutrients.iter() {
-            string.push_str(&format!("  {}\n", nutrient));
-        }
-
-        write!(f, "{}", string.replace('"', "").replace(" null", ""))
+        write!(f, "{}", self.format(&QuantityFormat::default()))
     }
 }
 
@@ -225,12 +386,17 @@ impl serde::Serialize for RecipeText {
     where
         S: serde::Serializer,
     {
-        let mut st = serializer.serialize_struct("RecipeText", 5)?;
+        let mut st = serializer.serialize_struct("RecipeText", 5 + self.region.is_some() as usize)?;
         st.serialize_field("title", &self.title.replace('"', ""))?;
-        st.serialize_field("prep_time", &self.prep_time.replace('"', ""))?;
+        st.serialize_field("times", &self.times)?;
         st.serialize_field("author_name", &self.author_name.replace('"', ""))?;
         st.serialize_field("origin", &self.origin.replace('"', ""))?;
         st.serialize_field("description", &self.description.replace('"', ""))?;
+        if let Some(region) = self.region {
+            st.serialize_field("region", &region)?;
+        } else {
+            st.skip_field("region")?;
+        }
         st.end()
     }
 }
@@ -238,12 +404,29 @@ impl serde::Serialize for RecipeText {
 impl std::ops::Mul<MixedRational> for RecipeItem {
     type Output = RecipeItem;
     fn mul(self, rhs: MixedRational) -> Self::Output {
+        // Nothing to scale in "salt, to taste"
+        if !self.is_measured() {
+            return self;
+        }
         RecipeItem {
             name: self.name,
             measure: self.measure * rhs,
             measure_b: self.measure_b.map(|b| b * rhs),
             note: self.note,
             plural: self.plural,
+            // Scaling changes how many containers, not how big they are
+            container_size: self.container_size,
+            alternatives: self
+                .alternatives
+                .into_iter()
+                .map(|a| Alternative {
+                    name: a.name,
+                    measure: a.measure.map(|m| m * rhs),
+                })
+                .collect(),
+            flags: self.flags,
+            // The sub-recipe follows from the scaled amount when resolved
+            recipe: self.recipe,
         }
     }
 }
@@ -255,8 +438,9 @@ impl std::ops::Mul<MixedRational> for RecipeData {
             original_servings: self.original_servings,
             servings: self.servings * rhs,
             ingredients: self.ingredients.iter().map(|i| i.clone() * rhs).collect(),
-            directions: self.directions,
-            nutrition_info: self.nutrition_info,
+            ingredient_groups: self.ingredient_groups,
+            directions: self.directions.into_iter().map(|d| d * rhs).collect(),
+            nutrition_info: self.nutrition_info * rhs,
         }
     }
 }
@@ -272,8 +456,212 @@ fn remove_duplicate_chars(s: &str, chars: &[char]) -> String {
     });
     s
 }
+fn parse_container_size(note: &str) -> Option<MeasureType> {
+    let note = note.trim();
+    let split = note.find(|c: char| c.is_alphabetic())?;
+    let (count, unit) = note.split_at(split);
+    let count = count.trim().trim_end_matches('-').parse::<MixedRational>().ok()?;
+    let unit = Measure::new(unit.trim().to_lowercase());
+    (unit.dimension().is_some() && !count.is_zero()).then_some(MeasureType { count, unit })
+}
+// "or" that says how much rather than what else, ex: "salt or more to taste"
+const AMOUNT_HINTS: &[&str] = &["more", "less", "as desired"];
+
+/// The first option of a line, the others as alternatives and any notes on the amount
+fn split_alternatives(line: &str, region: Option<UnitType>) -> (String, Vec<Alternative>, Vec<String>) {
+    let mut parts = line.split(" or ").map(str::trim);
+    let mut main = parts.next().unwrap_or_default().to_string();
+    let (mut alternatives, mut notes) = (Vec::new(), Vec::new());
+    for part in parts.filter(|p| !p.is_empty()) {
+        let counted = part.starts_with(char::is_numeric);
+        if AMOUNT_HINTS.iter().any(|h| part.starts_with(h)) {
+            notes.push(format!("or {}", part));
+        } else if counted && !main.contains(char::is_alphabetic) {
+            // A range written out, ex: "1 or 2 cloves garlic"
+            main = format!("{}-{}", main, part);
+        } else if counted {
+            if let Some(item) = RecipeItem::parse(part, region) {
+                alternatives.push(Alternative {
+                    name: item.name.trim().to_string(),
+                    measure: Some(item.measure),
+                });
+            }
+        } else {
+            alternatives.push(Alternative {
+                name: part.to_string(),
+                measure: None,
+            });
+        }
+    }
+    (main, alternatives, notes)
+}
+impl RecipeItem {
+    /// Parse an ingredient line, ex: "2 (14.5 oz) cans diced tomatoes", units are read as the
+    /// versions used in `region`
+    pub fn parse(line: &str, region: Option<UnitType>) -> Option<RecipeItem> {
+        let mut ingredient_str = line.to_string();
+        // Remove duplicate spaces and parenthesis
+        ingredient_str = remove_duplicate_chars(ingredient_str.trim(), &[' ', '(', ')']);
+        // Find where parenthesis are
+        let parens = (ingredient_str.find('('), ingredient_str.find(')'));
+        // Assemble a note if there are parenthesis
+        let note: String = if let (Some(left), Some(right)) = parens {
+            if ingredient_str.as_bytes()[left + 1] == b',' {
+                "".into()
+            } else {
+                let new = ingredient_str[left + 1..right].into();
+                // Keep whatever follows, ex: the "can" in "1 (14.5 oz) can"
+                ingredient_str = remove_duplicate_chars(
+                    &format!("{} {}", &ingredient_str[..left], &ingredient_str[right + 1..]),
+                    &[' '],
+                );
+                new
+            }
+        } else {
+            "".into()
+        };
+        // Alternatives in parenthesis, ex: "1 cup butter (or margarine)"
+        let note = if let Some(alternative) = note.strip_prefix("or ") {
+            ingredient_str = format!("{} or {}", ingredient_str, alternative);
+            String::new()
+        } else {
+            note
+        };
+        // Remove unnecessary characters
+        ingredient_str = ingredient_str.replace(['"', ',', ';', '(', ')'], "");
+        // Break if we have nothing >.>
+        if ingredient_str == "null" {
+            return None;
+        }
+        // ex: "fresh parsley, for garnish (optional)"
+        let (rest, mut flags) = extract_flags(&ingredient_str);
+        let (main, alternatives, amount_notes) = split_alternatives(&rest, region);
+        ingredient_str = main;
+        let (note, note_flags) = extract_flags(&note);
+        for flag in note_flags {
+            if !flags.contains(&flag) {
+                flags.push(flag);
+            }
+        }
+        let note = std::iter::once(note)
+            .chain(amount_notes)
+            .filter(|n| !n.is_empty())
+            .collect::<Vec<_>>()
+            .join(", ");
+        /*
+            If the first few characters are not part of a rational, we dont need extra processing
+        */
+        let (first_rational_char, first_alpha) = (
+            ingredient_str.find(|c: char| MixedRational::valid_chars().contains(c)),
+            ingredient_str.find(|c: char| !MixedRational::valid_chars().contains(c)),
+        );
+        if let Some(rational_char) = first_rational_char {
+            if let Some(alpha) = first_alpha {
+                if alpha < rational_char {
+                    println!("Processed: {}", ingredient_str);
+                    println!();
+                    return Some(RecipeItem {
+                        measure: MeasureType {
+                            count: 0.into(),
+                            unit: Measure {
+                                unit: Unit::Other,
+                                names: &[""],
+                                fluid: false,
+                                system: UnitType::Traditional,
+                            },
+                        },
+                        measure_b: None,
+                        name: ingredient_str,
+                        note: (!note.is_empty()).then_some(note),
+                        plural: false,
+                        container_size: None,
+                        alternatives,
+                        flags,
+                        recipe: None,
+                    });
+                }
+            }
+        }
+        let ingredient = ingredient_str;
+        // Find the first non-rational character
+        let non_fract = ingredient.find(|c: char| c.is_alphabetic())?;
+        let (fract_str, unit_str) = ingredient.split_at(non_fract);
+        let (count, count_b) = MixedRational::from_string(fract_str.to_string());
+        let (name_str, unit_str): (String, String) = if let Some(space) = unit_str.find(' ') {
+            (
+                unit_str[space + 1..].into(),
+                // Ensure the unit name is letters only for lookup
+                unit_str[..space]
+                    .replace(|c: char| !c.is_ascii_alphabetic(), "")
+                    .to_lowercase(),
+            )
+        } else {
+            ("".into(), unit_str.into())
+        };
+        // The actual struct for the unit, providing useful methods
+        let mut struct_unit = Measure::new(unit_str.clone());
+        if let Some(region) = region {
+            struct_unit = struct_unit.in_region(region);
+        }
+        let ingredient = if struct_unit.unit as u8 == Unit::Other as u8 {
+            struct_unit.names = &[""];
+            // Concatenate unit and name as there is no actual unit here
+            let mut unit = unit_str;
+            unit.push(' ');
+            unit.push_str(&name_str);
+            RecipeItem {
+                measure: MeasureType {
+                    count,
+                    unit: struct_unit,
+                },
+                // If the recipe specifies a range, this is the upper limit
+                // ex: 2-3 oz of cream cheese
+                measure_b: count_b.map(|m| MeasureType {
+                    count: m,
+                    unit: struct_unit,
+                }),
+                name: unit,
+                note: if note.is_empty() { None } else { Some(note) },
+                plural: false,
+                container_size: None,
+                alternatives,
+                flags,
+                recipe: None,
+            }
+        } else {
+            // A note on a container is usually its size, ex: 1 (14.5 oz) can
+            let container_size = if struct_unit.unit.is_container() {
+                parse_container_size(&note)
+            } else {
+                None
+            };
+            let note = if container_size.is_some() { "".into() } else { note };
+            RecipeItem {
+                measure: MeasureType {
+                    count,
+                    unit: struct_unit,
+                },
+                // If the recipe specifies a range, this is the upper limit
+                // ex: 2-3 oz of cream cheese
+                measure_b: count_b.map(|m| MeasureType {
+                    count: m,
+                    unit: struct_unit,
+                }),
+                name: name_str,
+                note: if note.is_empty() { None } else { Some(note) },
+                plural: unit_str.ends_with("'s") || unit_str.ends_with('s'),
+                container_size,
+                alternatives,
+                flags,
+                recipe: None,
+            }
+        };
+        Some(ingredient)
+    }
+}
 impl From<(String, serde_json::Value)> for ParsedRecipe {
     fn from((url, value): (String, serde_json::Value)) -> Self {
+        let region = UnitType::from_origin(&url);
         let ingredients_txt = if let Some(ingredients) = value["recipeIngredient"].as_array() {
             ingredients.iter().map(|v| v.to_string()).collect()
         } else {
@@ -285,163 +673,48 @@ impl From<(String, serde_json::Value)> for ParsedRecipe {
         };
         // Init ingredients list
         let mut ingredients: Vec<RecipeItem> = Vec::new();
-        for mut ingredient_str in ingredients_txt {
-            // Remove duplicate spaces and parenthesis
-            ingredient_str = remove_duplicate_chars(ingredient_str.trim(), &[' ', '(', ')']);
-            // Find where parenthesis are
-            let parens = (ingredient_str.find('('), ingredient_str.find(')'));
-            // Assemble a note if there are parenthesis
-            let note: String = if let (Some(left), Some(right)) = parens {
-                if ingredient_str.as_bytes()[left + 1] == b',' {
-                    "".into()
-                } else {
-                    let new = ingredient_str[left + 1..right].into();
-                    ingredient_str = ingredient_str[..left].into();
-                    new
-                }
-            } else {
-                "".into()
-            };
-            // Remove unnecessary characters
-            ingredient_str = ingredient_str.replace(['"', ',', ';', '(', ')'], "");
-            // Break if we have nothing >.>
-            if ingredient_str == "null" {
+        let mut ingredient_groups: Vec<IngredientGroup> = Vec::new();
+        for ingredient_str in ingredients_txt {
+            // Some sites put their headings in with the ingredients, ex: "For the gravy:"
+            if let Some(name) = group_heading(&ingredient_str) {
+                ingredient_groups.push(IngredientGroup { name, ingredients: Vec::new(), section: None });
                 continue;
             }
-            /*
-                If the first few characters are not part of a rational, we dont need extra processing
-            */
-            let (first_rational_char, first_alpha) = (
-                ingredient_str.find(|c: char| MixedRational::valid_chars().contains(c)),
-                ingredient_str.find(|c: char| !MixedRational::valid_chars().contains(c)),
-            );
-            if let Some(rational_char) = first_rational_char {
-                if let Some(alpha) = first_alpha {
-                    if alpha < rational_char {
-                        println!("Processed: {}", ingredient_str);
-                        ingredients.push(RecipeItem {
-                            measure: MeasureType {
-                                count: 0.into(),
-                                unit: Measure {
-                                    unit: Unit::Other,
-                                    names: &[""],
-                                    fluid: false,
-                                },
-                            },
-                            measure_b: None,
-                            name: ingredient_str,
-                            note: None,
-                            plural: false,
-                        });
-                        println!();
-                        continue;
-                    }
+            if let Some(ingredient) = RecipeItem::parse(&ingredient_str, region) {
+                if let Some(group) = ingredient_groups.last_mut() {
+                    group.ingredients.push(ingredients.len());
                 }
+                ingredients.push(ingredient);
             }
-            // Just grab the first option if the recipe lists different alternatives
-            let ingredient = if let Some(or) = ingredient_str.find(" or ") {
-                ingredient_str.split_at(or).0.into()
-            } else {
-                ingredient_str
-            };
-            // Find the first non-rational character
-            let non_fract = ingredient.find(|c: char| c.is_alphabetic()).unwrap();
-            let (fract_str, unit_str) = ingredient.split_at(non_fract);
-            let (count, count_b) = MixedRational::from_string(fract_str.to_string());
-            let (name_str, unit_str): (String, String) = if let Some(space) = unit_str.find(' ') {
-                (
-                    unit_str[space + 1..].into(),
-                    // Ensure the unit name is letters only for lookup
-                    unit_str[..space]
-                        .replace(|c: char| !c.is_ascii_alphabetic(), "")
-                        .to_lowercase(),
-                )
-            } else {
-                ("".into(), unit_str.into())
-            };
-            // The actual struct for the unit, providing useful methods
-            let mut struct_unit = Measure::new(unit_str.clone());
-            let ingredient = if struct_unit.unit as u8 == Unit::Other as u8 {
-                struct_unit.names = &[""];
-                // Concatenate unit and name as there is no actual unit here
-                let mut unit = unit_str;
-                unit.push(' ');
-                unit.push_str(&name_str);
-                RecipeItem {
-                    measure: MeasureType {
-                        count,
-                        unit: struct_unit,
-                    },
-                    // If the recipe specifies a range, this is the upper limit
-                    // ex: 2-3 oz of cream cheese
-                    measure_b: count_b.map(|m| MeasureType {
-                        count: m,
-                        unit: struct_unit,
-                    }),
-                    name: unit,
-                    note: if note.is_empty() { None } else { Some(note) },
-                    plural: false,
-                }
-            } else {
-                RecipeItem {
-                    measure: MeasureType {
-                        count,
-                        unit: struct_unit,
-                    },
-                    // If the recipe specifies a range, this is the upper limit
-                    // ex: 2-3 oz of cream cheese
-                    measure_b: count_b.map(|m| MeasureType {
-                        count: m,
-                        unit: struct_unit,
-                    }),
-                    name: name_str,
-                    note: if note.is_empty() { None } else { Some(note) },
-                    plural: unit_str.ends_with("'s") || unit_str.ends_with('s'),
-                }
-            };
-            ingredients.push(ingredient);
-            //let range = ingredient.find('-');
         }
+        ingredient_groups.retain(|g| !g.ingredients.is_empty());
         let mut nutrition_info = Vec::new();
-        // "servingSize",
-        for field in [
-            "calories",
-            "carbohydrateContent",
-            "proteinContent",
-            "fatContent",
-            "saturatedFatContent",
-            "transFatContent",
-            "cholesterolContent",
-            "sodiumContent",
-            "fiberContent",
-            "sugarContent",
-            "unsaturatedFatContent",
-        ]
-        .iter()
-        {
-            let val = value["nutrition"][field].to_string().replace('"', "");
-            if let Some(space) = val.find(' ') {
-                let (whole, unit) = val.split_at(space);
-                if let Ok(v) = whole.replace(|c: char| !c.is_numeric(), "").parse::<i32>() {
-                    nutrition_info.push(RecipeItem {
-                        measure: MeasureType {
-                            count: MixedRational::whole(v),
-                            unit: Measure::new(unit.replace(' ', "")),
-                        },
-                        measure_b: None,
-                        name: String::from(*field),
-                        note: None,
-                        plural: false,
-                    });
-                }
+        for nutrient in Nutrient::ALL {
+            let Some(key) = nutrient.schema_key() else {
+                continue;
+            };
+            let val = value["nutrition"][key].to_string().replace('"', "");
+            // ex: "12.5 g", "250 calories" or just "250"
+            let split = val.find(|c: char| c.is_alphabetic() || c == 'µ').unwrap_or(val.len());
+            let (amount, unit) = val.split_at(split);
+            if let Ok(amount) = amount.trim().replace(',', "").parse::<MixedRational>() {
+                let unit = NutrientUnit::parse(unit).unwrap_or(nutrient.default_unit());
+                nutrition_info.push(NutrientAmount::new(nutrient, amount, unit));
             }
         }
         let mut new = Self::default();
         new.data.ingredients = ingredients;
+        new.data.ingredient_groups = ingredient_groups;
+        let (servings_size, servings_unit) = value["nutrition"]["servingSize"]
+            .as_str()
+            .and_then(parse_serving_size)
+            .unwrap_or_default();
         new.data.nutrition_info = NutritionInfo {
-            servings_size: MixedRational::default(),
-            servings_unit: "".into(),
+            servings_size,
+            servings_unit,
             nutrients: nutrition_info,
+            // schema.org nutrition is always for one serving
+            basis: NutritionBasis::PerServing,
         };
         //HowToSection
         let mut section = DirectionSection::default();
@@ -459,7 +732,7 @@ impl From<(String, serde_json::Value)> for ParsedRecipe {
                             for d in s_array {
                                 section
                                     .sections
-                                    .push(d["text"].to_string().replace('"', ""))
+                                    .push(d["text"].to_string().replace('"', "").into())
                             }
                         }
                     }
@@ -474,7 +747,7 @@ impl From<(String, serde_json::Value)> for ParsedRecipe {
                                         if end < colon {
                                             new_section
                                                 .sections
-                                                .push(s[..end].to_string().replace('"', ""));
+                                                .push(s[..end].to_string().replace('"', "").into());
                                             s = s[end + 2..].into();
                                             continue;
                                         }
@@ -493,12 +766,12 @@ impl From<(String, serde_json::Value)> for ParsedRecipe {
                                 } else if let Some(end) = s.find(". ") {
                                     new_section
                                         .sections
-                                        .push(s[..end].to_string().replace('"', ""));
+                                        .push(s[..end].to_string().replace('"', "").into());
                                     s = s[end + 2..].into();
                                 }
                                 i += 1;
                             }
-                            new_section.sections.push(s);
+                            new_section.sections.push(s.into());
                             directions.push(new_section);
                         } else if let Some(semi) = s.find(';') {
                             if s.split_at(semi).0.replace(|c: char| c != ' ', "").len()
@@ -517,14 +790,14 @@ impl From<(String, serde_json::Value)> for ParsedRecipe {
                                     } else if let Some(end) = s.find(". ") {
                                         new_section
                                             .sections
-                                            .push(s[..end].to_string().replace('"', ""));
+                                            .push(s[..end].to_string().replace('"', "").into());
                                         s = s[end + 2..].into();
                                     }
                                     i += 1;
                                 }
 
                                 if new_section.sections.is_empty() {
-                                    section.sections.push(s);
+                                    section.sections.push(s.into());
                                 } else {
                                     directions.push(new_section);
                                 }
@@ -532,7 +805,7 @@ impl From<(String, serde_json::Value)> for ParsedRecipe {
                                 directions.push(new_section);
                             }
                         } else {
-                            section.sections.push(s);
+                            section.sections.push(s.into());
                         }
                     }
                     _ => {}
@@ -559,6 +832,8 @@ impl From<(String, serde_json::Value)> for ParsedRecipe {
             }
         } */
         new.data.directions = directions;
+        new.data.link_ingredients();
+        new.data.align_group_sections();
 
         new.text.author_name = if let Some(arr) = value["author"].as_array() {
             arr[0]["name"].to_string()
@@ -569,6 +844,7 @@ impl From<(String, serde_json::Value)> for ParsedRecipe {
         new.text.description = value["description"].to_string();
         new.text.title = value["name"].to_string();
         new.text.origin = url;
+        new.text.region = region;
         if let Some(ryield) = value["recipeYield"].as_array() {
             let s = ryield[0].to_string();
             new.data.servings = MixedRational::from_string(s).0;
@@ -583,39 +859,8 @@ impl From<(String, serde_json::Value)> for ParsedRecipe {
             .as_array()
             .map_or(Vec::new(), |m| m.iter().map(|v| v.to_string()).collect());
 
-        new.text.prep_time = if let Some(time) = [
-            value["totalTime"].to_string(),
-            value["performTime"].to_string(),
-            value["cookTime"].to_string(),
-            value["prepTime"].to_string(),
-        ]
-        .iter()
-        .find(|s| !s.contains("null"))
-        {
-            let mut t = time.replace('"', "");
-            if t.contains("PT") {
-                let stime = t.replace(|c: char| !c.is_numeric(), "");
-                if let Ok(minutes) = stime.parse::<usize>() {
-                    let (h, m) = (minutes / 60, minutes % 60);
-                    t = format!(
-                        "{} {}",
-                        match h {
-                            0 => "".into(),
-                            1 => format!("{} hour", h),
-                            _ => format!("{} hours", h),
-                        },
-                        match m {
-                            0 => "".into(),
-                            1 => format!("{} minute", m),
-                            _ => format!("{} minutes", m),
-                        },
-                    );
-                }
-            }
-            t
-        } else {
-            "".into()
-        };
+        new.text.times = RecipeTimes::from_json_ld(&value);
+        new.analyze_dietary(DietaryRules::shared());
 
         std::fs::write(
             format!("recipes/wip/{}.json", new.text.title.replace('"', "")),
@@ -0,0 +1,155 @@
+use crate::{Alternative, IngredientCatalog, IngredientFlag, MeasureType, ParsedRecipe, QuantityFormat, RecipeItem};
+
+/// Ingredients of several recipes added together
+#[derive(Clone, Debug, Default)]
+pub struct ShoppingList {
+    pub items: Vec<RecipeItem>,
+    /// Ingredients to buy when a recipe gives a choice, ex: margarine for "butter or margarine"
+    pub preferred: Vec<String>,
+    /// Things to have on hand without an amount to buy, ex: salt to taste, parsley for garnish
+    pub if_missing: Vec<RecipeItem>,
+}
+
+impl ShoppingList {
+    pub fn from_recipes<'a, I>(recipes: I) -> Self
+    where
+        I: IntoIterator<Item = &'a ParsedRecipe>,
+    {
+        let mut list = ShoppingList::default();
+        for recipe in recipes {
+            list.add_recipe(recipe);
+        }
+        list
+    }
+    pub fn add_recipe(&mut self, recipe: &ParsedRecipe) {
+        for item in recipe.data.ingredients.iter() {
+            self.add(item.clone());
+        }
+    }
+    /// Adds onto the same ingredient in a compatible unit, or a new line otherwise
+    pub fn add(&mut self, item: RecipeItem) {
+        let mut item = item.prefer(&self.preferred);
+        let catalog = IngredientCatalog::shared();
+        let name = catalog.key(&item.name);
+        if item.buy_if_missing() {
+            let listed = self.items.iter().chain(self.if_missing.iter()).any(|i| catalog.key(&i.name) == name);
+            if !listed {
+                self.if_missing.push(item);
+            }
+            return;
+        }
+        // Bought as one amount however the recipe splits it up
+        item.flags.retain(|f| *f != IngredientFlag::Divided);
+        self.if_missing.retain(|i| catalog.key(&i.name) != name);
+        for existing in self.items.iter_mut() {
+            if catalog.key(&existing.name) != name {
+                continue;
+            }
+            if let Some(merged) = merge(existing, &item) {
+                *existing = merged;
+                return;
+            }
+        }
+        self.items.push(item);
+    }
+    pub fn format(&self, fmt: &QuantityFormat) -> String {
+        let mut s = self
+            .items
+            .iter()
+            .map(|i| format!("- {}\n", fmt.item(i)))
+            .collect::<String>();
+        if !self.if_missing.is_empty() {
+            s.push_str("\nIf missing:\n");
+            for item in self.if_missing.iter() {
+                s.push_str(&format!("- {}\n", item.name.trim()));
+            }
+        }
+        s
+    }
+}
+
+impl std::fmt::Display for ShoppingList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.format(&QuantityFormat::default()))
+    }
+}
+
+/// `b` added into the units of `a`, None if they can't be added
+fn merge(a: &RecipeItem, b: &RecipeItem) -> Option<RecipeItem> {
+    // Containers only add up when they're the same size, 1 small can + 1 big can isn't 2 cans
+    if a.container_size.is_some() || b.container_size.is_some() {
+        let (Some(x), Some(y)) = (a.container_size, b.container_size) else {
+            return None;
+        };
+        if a.measure.unit.unit != b.measure.unit.unit || y.unit.convert(y.count, x.unit)? != x.count {
+            return None;
+        }
+    }
+    let add = |x: MeasureType, y: MeasureType| {
+        y.unit.convert(y.count, x.unit).map(|count| MeasureType {
+            count: x.count + count,
+            unit: x.unit,
+        })
+    };
+    let measure = add(a.measure, b.measure)?;
+    let measure_b = if a.measure_b.is_some() || b.measure_b.is_some() {
+        Some(add(
+            a.measure_b.unwrap_or(a.measure),
+            b.measure_b.unwrap_or(b.measure),
+        )?)
+    } else {
+        None
+    };
+    // A choice only stays when both lines offer the same one
+    let alternatives = merge_alternatives(&a.alternatives, &b.alternatives, add).unwrap_or_default();
+    Some(RecipeItem {
+        measure,
+        measure_b,
+        plural: a.plural || b.plural,
+        alternatives,
+        ..a.clone()
+    })
+}
+
+fn merge_alternatives<F>(a: &[Alternative], b: &[Alternative], add: F) -> Option<Vec<Alternative>>
+where
+    F: Fn(MeasureType, MeasureType) -> Option<MeasureType>,
+{
+    let catalog = IngredientCatalog::shared();
+    if a.len() != b.len() {
+        return None;
+    }
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| {
+            if catalog.key(&x.name) != catalog.key(&y.name) {
+                return None;
+            }
+            let measure = match (x.measure, y.measure) {
+                (None, None) => None,
+                (Some(m), Some(n)) => Some(add(m, n)?),
+                _ => return None,
+            };
+            Some(Alternative {
+                name: x.name.clone(),
+                measure,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measured_flour_with_extra_is_bought() {
+        let mut list = ShoppingList::default();
+        list.add(RecipeItem::parse("1/2 cup flour, plus more as needed", None).unwrap());
+        list.add(RecipeItem::parse("salt, to taste", None).unwrap());
+        assert_eq!(list.items.len(), 1);
+        assert_eq!(list.items[0].to_string().trim(), "1/2 cup flour");
+        assert_eq!(list.if_missing.len(), 1);
+        assert_eq!(list.if_missing[0].name.trim(), "salt");
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{normalize_ingredient_name, DirectionStep, RecipeData, RecipeItem};
+
+// Words describing an ingredient that steps leave out, ex: "the chicken" for boneless chicken breasts
+#[rustfmt::skip]
+const DESCRIPTORS: &[&str] = &[
+    "a", "an", "the", "of", "and", "or", "to", "for", "with", "plus", "about", "fresh", "freshly",
+    "chopped", "diced", "minced", "sliced", "grated", "shredded", "ground", "crushed", "cubed",
+    "peeled", "pitted", "trimmed", "halved", "quartered", "beaten", "melted", "softened", "cooked",
+    "uncooked", "raw", "dried", "frozen", "thawed", "canned", "large", "small", "medium", "whole",
+    "boneless", "skinless", "finely", "roughly", "thinly", "coarsely", "packed", "lightly", "extra",
+    "virgin", "unsalted", "salted", "room", "temperature", "cold", "warm", "hot", "divided",
+    "optional", "taste", "needed", "more", "good", "quality", "homemade", "store", "bought",
+];
+
+// The words of a name that a step might refer to it by
+pub(crate) fn core_words(name: &str) -> Vec<String> {
+    let name = name.split([',', '(']).next().unwrap_or_default();
+    normalize_ingredient_name(name)
+        .split(' ')
+        .filter(|w| !w.is_empty() && !DESCRIPTORS.contains(w))
+        .map(String::from)
+        .collect()
+}
+
+// Where `core` is mentioned in `words`, as spans of the longest part of it
+fn mentions(core: &[String], words: &[&str]) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i < words.len() {
+        // Longest run of the name starting here, ex: "chicken broth" over just "chicken"
+        let longest = (0..core.len())
+            .map(|c| {
+                core[c..]
+                    .iter()
+                    .zip(&words[i..])
+                    .take_while(|(a, b)| a == *b)
+                    .count()
+            })
+            .max()
+            .unwrap_or(0);
+        let long_enough = longest > 1 || (longest == 1 && words[i].len() >= 3);
+        if long_enough {
+            spans.push((i, i + longest));
+            i += longest;
+        } else {
+            i += 1;
+        }
+    }
+    spans
+}
+
+/// Indices of the ingredients mentioned in `text`, a mention inside a longer mention of another
+/// ingredient doesn't count, ex: "chicken broth" isn't the chicken
+pub fn find_ingredient_mentions(text: &str, ingredients: &[RecipeItem]) -> Vec<usize> {
+    let cores = ingredients.iter().map(|i| core_words(&i.name)).collect::<Vec<_>>();
+    let full = named_in_full(&[text], &cores);
+    mentions_in(text, &cores, &full)
+}
+
+// Whether each name appears whole in any of `texts`
+fn named_in_full(texts: &[&str], cores: &[Vec<String>]) -> Vec<bool> {
+    let texts = texts.iter().map(|t| normalize_ingredient_name(t)).collect::<Vec<_>>();
+    cores
+        .iter()
+        .map(|core| {
+            let name = core.join(" ");
+            core.len() > 1 && texts.iter().any(|t| format!(" {} ", t).contains(&format!(" {} ", name)))
+        })
+        .collect()
+}
+
+fn mentions_in(text: &str, cores: &[Vec<String>], full: &[bool]) -> Vec<usize> {
+    let normalized = normalize_ingredient_name(text);
+    let words = normalized.split(' ').collect::<Vec<_>>();
+    let spans = cores
+        .iter()
+        .enumerate()
+        .flat_map(|(i, core)| mentions(core, &words).into_iter().map(move |span| (i, span)))
+        .collect::<Vec<_>>();
+    let mut found = spans
+        .iter()
+        .filter(|(i, (start, end))| {
+            !spans.iter().any(|(j, (s, e))| {
+                let longer = s <= start && e >= end && (e - s) > (end - start);
+                // Both only partly named here, the one called by its full name elsewhere isn't meant
+                let same = (s, e) == (start, end) && full[*i] && !full[*j] && end - start < cores[*i].len();
+                j != i && (longer || same)
+            })
+        })
+        .map(|(i, _)| *i)
+        .collect::<Vec<_>>();
+    found.sort();
+    found.dedup();
+    found
+}
+
+impl RecipeData {
+    /// Record on every step which ingredients it uses
+    pub fn link_ingredients(&mut self) {
+        let cores = self.ingredients.iter().map(|i| core_words(&i.name)).collect::<Vec<_>>();
+        let texts = self
+            .directions
+            .iter()
+            .flat_map(|s| s.sections.iter())
+            .map(|step| step.text.as_str())
+            .collect::<Vec<_>>();
+        let full = named_in_full(&texts, &cores);
+        for step in self.directions.iter_mut().flat_map(|s| s.sections.iter_mut()) {
+            step.ingredients = mentions_in(&step.text, &cores, &full);
+        }
+    }
+    pub fn is_linked(&self) -> bool {
+        self.directions
+            .iter()
+            .flat_map(|s| s.sections.iter())
+            .any(|step| !step.ingredients.is_empty())
+    }
+    /// The ingredients a step uses, at this recipe's quantities
+    pub fn step_ingredients<'a>(&'a self, step: &'a DirectionStep) -> impl Iterator<Item = &'a RecipeItem> {
+        step.ingredients.iter().filter_map(|i| self.ingredients.get(*i))
+    }
+    /// Indices of ingredients no step mentions
+    pub fn unused_ingredients(&self) -> Vec<usize> {
+        (0..self.ingredients.len())
+            .filter(|i| {
+                !self
+                    .directions
+                    .iter()
+                    .flat_map(|s| s.sections.iter())
+                    .any(|step| step.ingredients.contains(i))
+            })
+            .collect()
+    }
+}
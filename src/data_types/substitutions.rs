@@ -0,0 +1,336 @@
+use std::{fmt, str::FromStr, sync::OnceLock};
+
+use crate::{
+    DensityTable, DietaryRules, DietaryTag, FoodGroup, IngredientCatalog, MeasureType, MixedRational, ParsedRecipe,
+    QuantityFormat, RecipeData, RecipeItem, MAX_DENOMINATOR,
+};
+
+#[rustfmt::skip]
+const BUNDLED_RULES: &[&str] = &[
+    "1 cup buttermilk = 1 cup milk + 1 tbsp lemon juice",
+    "1 cup buttermilk = 1 cup oat milk + 1 tbsp lemon juice",
+    "1 cup milk = 1 cup oat milk",
+    "1 cup heavy cream = 1 cup coconut cream",
+    "1 cup sour cream = 1 cup plain yogurt",
+    "1 cup sour cream = 1 cup coconut cream + 1 tbsp lemon juice",
+    "1 cup butter = 1 cup vegan butter",
+    "1 cup parmesan = 1/2 cup nutritional yeast",
+    "1 egg = 1 tbsp ground flaxseed + 3 tbsp water",
+    "1 cup all-purpose flour = 1 cup gluten-free all-purpose flour",
+    "1 tbsp soy sauce = 1 tbsp tamari",
+    "1 tbsp soy sauce = 1 tbsp coconut aminos",
+    "1 cup honey = 1 cup maple syrup",
+    "1 cup chicken broth = 1 cup vegetable broth",
+    "1 cup chicken stock = 1 cup vegetable stock",
+    "1 cup beef broth = 1 cup vegetable broth",
+    "1 tbsp fish sauce = 1 tbsp soy sauce",
+    "1 tsp baking powder = 1/4 tsp baking soda + 1/2 tsp cream of tartar",
+    "1 tbsp cornstarch = 2 tbsp all-purpose flour",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseSubstitutionErrorKind {
+    /// Rules look like "1 cup buttermilk = 1 cup milk + 1 tbsp lemon juice"
+    MissingEquals,
+    InvalidIngredient,
+    /// The replaced amount has to be more than nothing to scale from it
+    ZeroAmount,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSubstitutionError {
+    pub token: String,
+    pub reason: ParseSubstitutionErrorKind,
+}
+
+impl fmt::Display for ParseSubstitutionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.reason {
+            ParseSubstitutionErrorKind::MissingEquals => "expected an \"=\" between the ingredient and its replacement",
+            ParseSubstitutionErrorKind::InvalidIngredient => "invalid ingredient",
+            ParseSubstitutionErrorKind::ZeroAmount => "the replaced amount can't be zero",
+        };
+        write!(f, "{}: \"{}\"", reason, self.token)
+    }
+}
+
+impl std::error::Error for ParseSubstitutionError {}
+
+/// One way to replace an ingredient, the replacements are for exactly `original`
+#[derive(Clone, Debug)]
+pub struct SubstitutionRule {
+    pub original: RecipeItem,
+    pub replacements: Vec<RecipeItem>,
+}
+
+impl FromStr for SubstitutionRule {
+    type Err = ParseSubstitutionError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use ParseSubstitutionErrorKind::*;
+        let (original, replacements) = s
+            .split_once('=')
+            .ok_or_else(|| ParseSubstitutionError { token: s.into(), reason: MissingEquals })?;
+        let parse = |line: &str| {
+            RecipeItem::parse(line, None)
+                .filter(|item| !item.name.trim().is_empty())
+                .ok_or_else(|| ParseSubstitutionError { token: line.trim().into(), reason: InvalidIngredient })
+        };
+        let original = parse(original)?;
+        if original.measure.count.is_zero() {
+            return Err(ParseSubstitutionError { token: original.to_string(), reason: ZeroAmount });
+        }
+        Ok(SubstitutionRule {
+            original,
+            replacements: replacements.split('+').map(parse).collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl fmt::Display for SubstitutionRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let replacements = self.replacements.iter().map(|r| r.to_string()).collect::<Vec<_>>();
+        write!(f, "{} = {}", self.original, replacements.join(" + "))
+    }
+}
+
+impl SubstitutionRule {
+    pub fn matches(&self, item: &RecipeItem) -> bool {
+        let catalog = IngredientCatalog::shared();
+        catalog.key(&self.original.name) == catalog.key(&item.name)
+    }
+    // How many times the rule's amount `measure` is, going through density for cups of something weighed
+    fn ratio(&self, measure: MeasureType, name: &str) -> Option<MixedRational> {
+        let density = DensityTable::shared().lookup(name);
+        let converted = measure.convert_with_density(self.original.measure.unit.unit, density)?;
+        let ratio = converted.count / self.original.measure.count;
+        // Converting leaves ratios like 1 1/69 for 240 ml, a sixteenth is as close as a substitute needs
+        if measure.unit.unit == self.original.measure.unit.unit {
+            Some(ratio.limit_denominator(MAX_DENOMINATOR))
+        } else {
+            Some(ratio.limit_denominator(16))
+        }
+    }
+    /// The replacements for `item` at its amount, noting what they replace. None if it isn't the
+    /// rule's ingredient or the amounts can't be compared
+    pub fn apply(&self, item: &RecipeItem) -> Option<Vec<RecipeItem>> {
+        if !self.matches(item) {
+            return None;
+        }
+        let in_containers = |m: MeasureType| match item.container_size {
+            Some(size) => MeasureType { count: size.count * m.count, unit: size.unit },
+            None => m,
+        };
+        let ratio = self.ratio(in_containers(item.measure), &item.name)?;
+        let ratio_b = match item.measure_b {
+            Some(b) => Some(self.ratio(in_containers(b), &item.name)?),
+            None => None,
+        };
+        let note = format!("instead of {}", item.format(&QuantityFormat::default()).trim());
+        Some(
+            self.replacements
+                .iter()
+                .map(|r| {
+                    let mut replacement = r.clone() * ratio;
+                    // A replacement's own range stays unless the original's gives it one
+                    if let Some(b) = ratio_b {
+                        replacement.measure_b = Some(r.measure * b);
+                    }
+                    // Optional butter makes for optional vegan butter
+                    for flag in item.flags.iter() {
+                        if !replacement.flags.contains(flag) {
+                            replacement.flags.push(*flag);
+                        }
+                    }
+                    replacement.note = Some(note.clone());
+                    replacement
+                })
+                .collect(),
+        )
+    }
+    /// Food groups the replacements are in
+    pub fn groups(&self, rules: &DietaryRules) -> Vec<FoodGroup> {
+        self.replacements
+            .iter()
+            .flat_map(|r| rules.classify(&r.name))
+            .map(|(group, _)| group)
+            .collect()
+    }
+}
+
+/// Substitution rules, tried in order
+#[derive(Clone, Debug, Default)]
+pub struct SubstitutionRules {
+    pub rules: Vec<SubstitutionRule>,
+}
+
+impl SubstitutionRules {
+    pub fn bundled() -> Self {
+        SubstitutionRules {
+            rules: BUNDLED_RULES.iter().filter_map(|r| r.parse().ok()).collect(),
+        }
+    }
+    /// The bundled rules, built once and shared
+    pub fn shared() -> &'static Self {
+        static RULES: OnceLock<SubstitutionRules> = OnceLock::new();
+        RULES.get_or_init(SubstitutionRules::bundled)
+    }
+    pub fn add(&mut self, rule: &str) -> Result<(), ParseSubstitutionError> {
+        self.rules.push(rule.parse()?);
+        Ok(())
+    }
+    /// Add rules from a JSON list, ex: ["1 cup milk = 1 cup soy milk"]
+    pub fn extend_from_file<P>(&mut self, path: P) -> Result<(), Box<dyn std::error::Error>>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let rules: Vec<String> = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        for rule in rules {
+            self.add(&rule)?;
+        }
+        Ok(())
+    }
+    pub fn for_ingredient<'a>(&'a self, item: &'a RecipeItem) -> impl Iterator<Item = &'a SubstitutionRule> {
+        self.rules.iter().filter(move |r| r.matches(item))
+    }
+}
+
+/// A substitution made in a recipe
+#[derive(Clone, Debug)]
+pub struct Substitution {
+    pub original: RecipeItem,
+    pub replacements: Vec<RecipeItem>,
+}
+
+/// A recipe changed to fit a diet, `unresolved` are the ingredients (by index in `recipe`) no rule
+/// could replace
+#[derive(Clone, Debug)]
+pub struct DietaryAdaptation {
+    pub recipe: ParsedRecipe,
+    pub substitutions: Vec<Substitution>,
+    pub unresolved: Vec<usize>,
+}
+
+impl RecipeData {
+    /// Put `replacements` where ingredient `index` was, keeping the steps pointing at the right ingredients
+    pub fn replace_ingredient(&mut self, index: usize, replacements: Vec<RecipeItem>) {
+        let added = replacements.len();
+        self.ingredients.splice(index..=index, replacements);
+        let remap = |indices: &[usize]| {
+            indices
+                .iter()
+                .flat_map(|i| match (*i).cmp(&index) {
+                    std::cmp::Ordering::Less => *i..*i + 1,
+                    std::cmp::Ordering::Equal => index..index + added,
+                    std::cmp::Ordering::Greater => *i + added - 1..*i + added,
+                })
+                .collect()
+        };
+        for step in self.directions.iter_mut().flat_map(|s| s.sections.iter_mut()) {
+            step.ingredients = remap(&step.ingredients);
+        }
+        for group in self.ingredient_groups.iter_mut() {
+            group.ingredients = remap(&group.ingredients);
+        }
+    }
+}
+
+impl ParsedRecipe {
+    /// Replace ingredient `index` using `rule`, None if the rule doesn't fit it
+    pub fn apply_substitution(&self, index: usize, rule: &SubstitutionRule) -> Option<(Self, Substitution)> {
+        let original = self.data.ingredients.get(index)?.clone();
+        let replacements = rule.apply(&original)?;
+        let mut new = self.clone();
+        new.data.replace_ingredient(index, replacements.clone());
+        new.analyze_dietary(DietaryRules::shared());
+        Some((new, Substitution { original, replacements }))
+    }
+    /// Replace every use of `ingredient` with the first rule that fits, ex: out of buttermilk
+    pub fn substitute(&self, ingredient: &str, rules: &SubstitutionRules) -> (Self, Vec<Substitution>) {
+        let key = IngredientCatalog::shared().key(ingredient);
+        let mut new = self.clone();
+        let mut substitutions = Vec::new();
+        for index in (0..self.data.ingredients.len()).rev() {
+            let item = new.data.ingredients[index].clone();
+            if IngredientCatalog::shared().key(&item.name) != key {
+                continue;
+            }
+            let applied = rules.for_ingredient(&item).find_map(|r| new.apply_substitution(index, r));
+            if let Some((recipe, substitution)) = applied {
+                new = recipe;
+                substitutions.insert(0, substitution);
+            }
+        }
+        (new, substitutions)
+    }
+    /// Swap out everything that keeps the recipe from being `tag`, ex: make this dairy-free
+    pub fn make(&self, tag: DietaryTag, rules: &SubstitutionRules, dietary: &DietaryRules) -> DietaryAdaptation {
+        let excluded = tag.excluded_groups();
+        let breaks = |groups: &[FoodGroup]| groups.iter().any(|g| excluded.contains(g));
+        let item_breaks = |item: &RecipeItem| breaks(&dietary.classify(&item.name).into_iter().map(|(g, _)| g).collect::<Vec<_>>());
+        let mut recipe = self.clone();
+        let mut substitutions = Vec::new();
+        for index in (0..self.data.ingredients.len()).rev() {
+            let item = recipe.data.ingredients[index].clone();
+            if !item_breaks(&item) {
+                continue;
+            }
+            let applied = rules
+                .for_ingredient(&item)
+                .filter(|r| !breaks(&r.groups(dietary)))
+                .find_map(|r| recipe.apply_substitution(index, r));
+            if let Some((new, substitution)) = applied {
+                recipe = new;
+                substitutions.insert(0, substitution);
+            }
+        }
+        recipe.analyze_dietary(dietary);
+        let unresolved = (0..recipe.data.ingredients.len())
+            .filter(|i| item_breaks(&recipe.data.ingredients[*i]))
+            .collect();
+        DietaryAdaptation {
+            recipe,
+            substitutions,
+            unresolved,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IngredientFlag, IngredientGroup};
+
+    #[test]
+    fn replacements_keep_flags_and_ranges() {
+        let rule: SubstitutionRule = "1 cup butter = 1 cup vegan butter + 1-2 tbsp water".parse().unwrap();
+        let item = RecipeItem::parse("2 cups butter, optional", None).unwrap();
+        assert!(item.has_flag(IngredientFlag::Optional));
+        let replacements = rule.apply(&item).unwrap();
+        assert!(replacements.iter().all(|r| r.has_flag(IngredientFlag::Optional)));
+        let water = &replacements[1];
+        assert_eq!(water.measure.count, MixedRational::whole(2));
+        assert_eq!(water.measure_b.map(|b| b.count), Some(MixedRational::whole(4)));
+
+        let ranged = RecipeItem::parse("1-2 cups butter", None).unwrap();
+        let replacements = rule.apply(&ranged).unwrap();
+        assert_eq!(replacements[0].measure_b.map(|b| b.count), Some(MixedRational::whole(2)));
+    }
+
+    #[test]
+    fn replacements_stay_in_the_group() {
+        let mut recipe = ParsedRecipe::default();
+        recipe.data.ingredients = ["1 cup sugar", "1 cup buttermilk", "1 tsp salt"]
+            .iter()
+            .filter_map(|l| RecipeItem::parse(l, None))
+            .collect();
+        recipe.data.ingredient_groups = vec![IngredientGroup {
+            name: "For the batter".into(),
+            ingredients: vec![1, 2],
+            section: None,
+        }];
+        let rule: SubstitutionRule = "1 cup buttermilk = 1 cup milk + 1 tbsp lemon juice".parse().unwrap();
+        let (new, _) = recipe.apply_substitution(1, &rule).unwrap();
+        assert_eq!(new.data.ingredient_groups[0].ingredients, vec![1, 2, 3]);
+        assert_eq!(new.data.ingredients[2].name, "lemon juice");
+    }
+}
@@ -0,0 +1,344 @@
+use crate::{MixedRational, ParsedRecipe, Unit, UnitType};
+
+/// Fan ovens cook hotter, so recipes written for them are this much cooler
+pub const FAN_OFFSET_CELSIUS: i64 = 20;
+
+#[rustfmt::skip]
+const GAS_MARKS: [[i64; 2]; 12] = [
+    [1, 4], [1, 2], [1, 1], [2, 1], [3, 1], [4, 1], [5, 1], [6, 1], [7, 1], [8, 1], [9, 1], [10, 1],
+];
+
+/// An oven or cooking temperature, ex: 350°F, 160°C fan, gas mark 4
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Temperature {
+    pub degrees: MixedRational,
+    /// `Unit::Fahrenheit`, `Unit::Celsius` or `Unit::GasMark`
+    pub unit: Unit,
+    /// Written for a fan (convection) oven
+    pub fan: bool,
+}
+
+impl Temperature {
+    pub fn new(degrees: MixedRational, unit: Unit) -> Self {
+        Temperature {
+            degrees,
+            unit,
+            fan: false,
+        }
+    }
+    /// The same setting in celsius for a conventional oven
+    pub fn celsius(&self) -> Option<MixedRational> {
+        let celsius = match self.unit {
+            Unit::Celsius => self.degrees,
+            Unit::Fahrenheit => fahrenheit_to_celsius(self.degrees),
+            Unit::GasMark => fahrenheit_to_celsius(gas_mark_to_fahrenheit(self.degrees)),
+            _ => return None,
+        };
+        Some(if self.fan {
+            celsius + MixedRational::whole(FAN_OFFSET_CELSIUS)
+        } else {
+            celsius
+        })
+    }
+    /// Exact conversion, gas marks snap to the nearest mark and are never fan, None if
+    /// `unit` isn't a temperature
+    pub fn convert(&self, unit: Unit, fan: bool) -> Option<Self> {
+        let celsius = self.celsius()?;
+        let fan = fan && unit != Unit::GasMark;
+        let celsius = if fan {
+            celsius - MixedRational::whole(FAN_OFFSET_CELSIUS)
+        } else {
+            celsius
+        };
+        let degrees = match unit {
+            Unit::Celsius => celsius,
+            Unit::Fahrenheit => celsius_to_fahrenheit(celsius),
+            Unit::GasMark => {
+                let fahrenheit = celsius_to_fahrenheit(celsius);
+                // Below the lowest mark isn't an oven setting, ex: a meat thermometer reading
+                if fahrenheit < MixedRational::whole(200) {
+                    return None;
+                }
+                MixedRational::from_frac_list(GAS_MARKS.to_vec())
+                    .into_iter()
+                    .min_by_key(|m| (gas_mark_to_fahrenheit(*m) - fahrenheit).abs())?
+            }
+            _ => return None,
+        };
+        Some(Temperature { degrees, unit, fan })
+    }
+    /// Round to what a dial can be set to, every 10°C or 25°F for ovens and whole degrees below that
+    pub fn rounded(&self) -> Self {
+        let step = match self.unit {
+            Unit::Celsius if self.degrees >= MixedRational::whole(120) => 10,
+            Unit::Fahrenheit if self.degrees >= MixedRational::whole(250) => 25,
+            Unit::GasMark => return *self,
+            _ => 1,
+        };
+        let step = MixedRational::whole(step);
+        Temperature {
+            degrees: (self.degrees / step + MixedRational::fract(1, 2)).floor() * step,
+            ..*self
+        }
+    }
+}
+
+impl std::fmt::Display for Temperature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fan = if self.fan { " fan" } else { "" };
+        match self.unit {
+            Unit::Fahrenheit => write!(f, "{}°F{}", self.degrees, fan),
+            Unit::Celsius => write!(f, "{}°C{}", self.degrees, fan),
+            _ => write!(f, "gas mark {}", self.degrees),
+        }
+    }
+}
+
+fn fahrenheit_to_celsius(fahrenheit: MixedRational) -> MixedRational {
+    (fahrenheit - MixedRational::whole(32)) * MixedRational::fract(5, 9)
+}
+
+fn celsius_to_fahrenheit(celsius: MixedRational) -> MixedRational {
+    celsius * MixedRational::fract(9, 5) + MixedRational::whole(32)
+}
+
+// Mark 1 is 275°F and each mark adds 25°F, the quarter and half marks go down by 25°F
+fn gas_mark_to_fahrenheit(mark: MixedRational) -> MixedRational {
+    if mark >= MixedRational::whole(1) {
+        MixedRational::whole(250) + mark * MixedRational::whole(25)
+    } else {
+        MixedRational::whole(200) + mark * MixedRational::whole(100)
+    }
+}
+
+/// A temperature found in text, `range` is its byte span
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TemperatureMatch {
+    pub range: std::ops::Range<usize>,
+    pub temperature: Temperature,
+}
+
+const DEGREE_SIGNS: &[char] = &['°', 'º', '˚'];
+
+// A scale written out after the number, returns the unit and its length
+fn scale_word(rest: &str) -> Option<(Unit, usize)> {
+    [
+        ("fahrenheit", Unit::Fahrenheit),
+        ("celsius", Unit::Celsius),
+        ("centigrade", Unit::Celsius),
+        ("f", Unit::Fahrenheit),
+        ("c", Unit::Celsius),
+    ]
+    .iter()
+    .find(|(word, _)| {
+        rest.starts_with(word) && !rest[word.len()..].starts_with(|c: char| c.is_alphabetic())
+    })
+    .map(|(word, unit)| (*unit, word.len()))
+}
+
+// Whether a cooking temperature could be this many degrees, ex: 350°F but not 2°F
+fn plausible(degrees: MixedRational, unit: Unit) -> bool {
+    let (low, high) = match unit {
+        Unit::Fahrenheit => (90, 600),
+        _ => (30, 320),
+    };
+    degrees >= MixedRational::whole(low) && degrees <= MixedRational::whole(high)
+}
+
+fn skip_spaces(text: &str, i: usize) -> usize {
+    i + text[i..].len() - text[i..].trim_start().len()
+}
+
+// End of the number starting at `i`, a trailing period ends the sentence rather than the number
+fn number_end(text: &str, i: usize) -> usize {
+    let end = i + text[i..]
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '/' || "½¼¾⅓⅔".contains(c)))
+        .unwrap_or(text.len() - i);
+    i + text[i..end].trim_end_matches('.').len()
+}
+
+/// Find temperatures like "350°F", "180 degrees C", "160C fan" or "gas mark 4", bare degrees
+/// ("350 degrees") are read as `default_unit`
+pub fn find_temperatures(text: &str, default_unit: Unit) -> Vec<TemperatureMatch> {
+    let lower = text.to_ascii_lowercase();
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < lower.len() {
+        let rest = &lower[i..];
+        let boundary = !lower[..i].ends_with(|c: char| c.is_alphanumeric() || c == '.');
+        if boundary && rest.starts_with("gas mark") {
+            let start = skip_spaces(&lower, i + "gas mark".len());
+            let end = number_end(&lower, start);
+            if let Ok(mark) = lower[start..end].parse::<MixedRational>() {
+                found.push(TemperatureMatch {
+                    range: i..end,
+                    temperature: Temperature::new(mark, Unit::GasMark),
+                });
+            }
+            i = end.max(i + 1);
+            continue;
+        }
+        if !(boundary && rest.starts_with(|c: char| c.is_ascii_digit())) {
+            i += rest.chars().next().map_or(1, char::len_utf8);
+            continue;
+        }
+        // The number, and the upper end if it's a range like 350-375°F
+        let mut numbers = Vec::with_capacity(2);
+        numbers.push(i..number_end(&lower, i));
+        let mut j = numbers[0].end;
+        let dash = lower[j..].trim_start().chars().next().filter(|c| ['-', '–'].contains(c));
+        if let Some(dash) = dash {
+            let second = skip_spaces(&lower, skip_spaces(&lower, j) + dash.len_utf8());
+            if lower[second..].starts_with(|c: char| c.is_ascii_digit()) {
+                numbers.push(second..number_end(&lower, second));
+                j = numbers[1].end;
+            }
+        }
+        // The scale, ex: °F, ° C, degrees fahrenheit, 180C
+        let sign = lower[skip_spaces(&lower, j)..].chars().next().filter(|c| DEGREE_SIGNS.contains(c));
+        let unit = if let Some(sign) = sign {
+            j = skip_spaces(&lower, j) + sign.len_utf8();
+            let after = skip_spaces(&lower, j);
+            Some(scale_word(&lower[after..]).map_or(default_unit, |(unit, len)| {
+                j = after + len;
+                unit
+            }))
+        } else if let Some(word) = ["degrees", "degree", "deg"]
+            .iter()
+            .find(|w| lower[skip_spaces(&lower, j)..].starts_with(*w))
+        {
+            j = skip_spaces(&lower, j) + word.len();
+            let after = skip_spaces(&lower, j);
+            Some(scale_word(&lower[after..]).map_or(default_unit, |(unit, len)| {
+                j = after + len;
+                unit
+            }))
+        } else {
+            // Right after the number or one space on, ex: 180C, 350 F, a lone letter only for oven-like
+            // numbers so "2c flour" stays cups
+            let after = j + lower[j..].starts_with(' ') as usize;
+            let degrees = lower[numbers[numbers.len() - 1].clone()].parse::<MixedRational>().ok();
+            scale_word(&lower[after..])
+                .filter(|(unit, len)| *len > 1 || degrees.is_some_and(|d| plausible(d, *unit)))
+                .map(|(unit, len)| {
+                    j = after + len;
+                    unit
+                })
+        };
+        let Some(unit) = unit else {
+            i = j;
+            continue;
+        };
+        // Fan ovens, ex: 160°C fan, 160°C (fan), fan 160°C
+        let mut start = i;
+        let after = skip_spaces(&lower, j);
+        let suffix = ["(fan)", "fan-forced", "fan forced", "fan"]
+            .iter()
+            .find(|f| lower[after..].starts_with(*f) && !lower[after + f.len()..].starts_with(|c: char| c.is_alphabetic()));
+        let prefix = lower[..i].trim_end().strip_suffix("fan").filter(|p| !p.ends_with(|c: char| c.is_alphabetic()));
+        let fan = if let Some(f) = suffix {
+            j = after + f.len();
+            true
+        } else if let Some(p) = prefix {
+            start = p.len();
+            true
+        } else {
+            false
+        };
+        let count = numbers.len();
+        for (n, range) in numbers.into_iter().enumerate() {
+            let Ok(degrees) = lower[range.clone()].parse::<MixedRational>() else {
+                continue;
+            };
+            found.push(TemperatureMatch {
+                range: if n == 0 { start } else { range.start }..if n + 1 == count { j } else { range.end },
+                temperature: Temperature { degrees, unit, fan },
+            });
+        }
+        i = j;
+    }
+    found
+}
+
+/// Rewrite every temperature in `text` into `unit`, rounded to what an oven can be set to
+pub fn rewrite_temperatures(text: &str, default_unit: Unit, unit: Unit, fan: bool) -> String {
+    let mut rewritten = text.to_string();
+    for m in find_temperatures(text, default_unit).iter().rev() {
+        let t = m.temperature;
+        if t.unit == unit && t.fan == fan {
+            continue;
+        }
+        if let Some(converted) = t.convert(unit, fan) {
+            rewritten.replace_range(m.range.clone(), &converted.rounded().to_string());
+        }
+    }
+    rewritten
+}
+
+impl UnitType {
+    /// The scale recipes from this system write temperatures in
+    pub fn temperature_unit(&self) -> Unit {
+        match self {
+            UnitType::UsCustomary | UnitType::UsLegal | UnitType::Traditional => Unit::Fahrenheit,
+            UnitType::Imperial | UnitType::Metric | UnitType::Australian => Unit::Celsius,
+        }
+    }
+}
+
+impl ParsedRecipe {
+    // Bare degrees are in the recipe's own scale, most recipes without a region are american
+    fn default_temperature_unit(&self) -> Unit {
+        self.text
+            .region
+            .map_or(Unit::Fahrenheit, |r| r.temperature_unit())
+    }
+    /// Every temperature mentioned in the directions, in order
+    pub fn temperatures(&self) -> Vec<Temperature> {
+        let default_unit = self.default_temperature_unit();
+        self.data
+            .directions
+            .iter()
+            .flat_map(|section| section.sections.iter())
+            .flat_map(|direction| find_temperatures(&direction.text, default_unit))
+            .map(|m| m.temperature)
+            .collect()
+    }
+    /// Rewrite the temperatures in the directions into `unit`, ex: "Preheat oven to 350°F" -> "Preheat oven to 180°C"
+    pub fn to_temperature_unit(&self, unit: Unit, fan: bool) -> Self {
+        let default_unit = self.default_temperature_unit();
+        let mut new = self.clone();
+        for section in new.data.directions.iter_mut() {
+            for direction in section.sections.iter_mut() {
+                direction.text = rewrite_temperatures(&direction.text, default_unit, unit, fan);
+            }
+        }
+        new
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_fahrenheit(text: &str) -> String {
+        rewrite_temperatures(text, Unit::Celsius, Unit::Fahrenheit, false)
+    }
+
+    #[test]
+    fn cups_glued_to_a_number_are_not_temperatures() {
+        assert_eq!(to_fahrenheit("Add 2c flour and stir."), "Add 2c flour and stir.");
+        assert_eq!(to_fahrenheit("Whisk in 1c milk"), "Whisk in 1c milk");
+        assert_eq!(to_fahrenheit("Whisk in 1 c milk"), "Whisk in 1 c milk");
+    }
+
+    #[test]
+    fn spaced_and_glued_scales() {
+        assert_eq!(to_fahrenheit("Preheat oven to 180 C."), "Preheat oven to 350°F.");
+        assert_eq!(to_fahrenheit("Preheat oven to 180C."), "Preheat oven to 350°F.");
+        let found = find_temperatures("Bake at 350 F for 20 minutes", Unit::Celsius);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].temperature, Temperature::new(MixedRational::whole(350), Unit::Fahrenheit));
+        assert_eq!(found[0].range, 8..13);
+        assert_eq!(to_fahrenheit("Heat to 180 degrees"), "Heat to 350°F");
+    }
+}
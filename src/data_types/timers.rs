@@ -0,0 +1,166 @@
+use std::time::Duration;
+
+use crate::{
+    deserialize_iso8601, mixed_rational::VULGAR_FRACTIONS, serialize_iso8601, MixedRational, RecipeData,
+};
+
+// Verbs a timer is named after, ex: "simmer for 10 minutes" is a simmer timer
+#[rustfmt::skip]
+const TIMER_VERBS: &[&str] = &[
+    "bake", "roast", "broil", "grill", "toast", "fry", "saute", "sauté", "sear", "brown", "simmer", "boil",
+    "poach", "steam", "braise", "cook", "heat", "microwave", "reduce", "rest", "cool", "chill", "refrigerate",
+    "freeze", "marinate", "soak", "rise", "proof", "knead", "beat", "whisk", "blend", "mix", "stir", "stand",
+    "sit", "set",
+];
+
+/// Steps starting with these run alongside the step before them
+const PARALLEL_MARKERS: &[&str] = &["meanwhile", "while", "at the same time", "in the meantime"];
+
+/// A duration mentioned in a step, ex: the 25 to 30 minutes in "bake for 25-30 minutes"
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Timer {
+    #[serde(serialize_with = "serialize_iso8601", deserialize_with = "deserialize_iso8601")]
+    pub min: Duration,
+    /// Same as `min` unless the step gives a range
+    #[serde(serialize_with = "serialize_iso8601", deserialize_with = "deserialize_iso8601")]
+    pub max: Duration,
+    /// What the timer is for, ex: bake, simmer
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub label: Option<String>,
+    /// The words the time was read from, ex: "25-30 minutes"
+    pub text: String,
+}
+
+impl Timer {
+    pub fn is_range(&self) -> bool {
+        self.max > self.min
+    }
+}
+
+fn time_unit(word: &str) -> Option<u64> {
+    match word.trim_end_matches([',', '.', ';', ':', ')', '!']).to_lowercase().as_str() {
+        "second" | "seconds" | "sec" | "secs" => Some(1),
+        "minute" | "minutes" | "min" | "mins" => Some(60),
+        "hour" | "hours" | "hr" | "hrs" => Some(3600),
+        "day" | "days" => Some(86400),
+        _ => None,
+    }
+}
+
+pub(crate) fn is_number(token: &str) -> bool {
+    token.contains(|c: char| c.is_ascii_digit() || VULGAR_FRACTIONS.iter().any(|v| v.0 == c))
+        && token
+            .chars()
+            .all(|c| c.is_ascii_digit() || "./-–".contains(c) || VULGAR_FRACTIONS.iter().any(|v| v.0 == c))
+}
+
+fn to_duration(q: MixedRational, seconds: u64) -> Duration {
+    let (n, d) = q.to_improper();
+    Duration::from_secs((n * seconds as i128 / d.max(1)).max(0) as u64)
+}
+
+// A single amount of time starting at token `k`, returns it with the next token and where it ends
+fn read_time(text: &str, tokens: &[(usize, &str)], k: usize) -> Option<(Duration, Duration, usize, usize)> {
+    let &(start, first) = tokens.get(k)?;
+    // Written as one word, ex: a 10-minute rest
+    if !is_number(first) {
+        let (number, unit) = first.rsplit_once('-')?;
+        let seconds = time_unit(unit).filter(|_| is_number(number))?;
+        let (a, b) = MixedRational::try_from_string(number).ok()?;
+        let end = start + first.trim_end_matches([',', '.', ';', ':', ')', '!']).len();
+        return Some((to_duration(a, seconds), to_duration(b.unwrap_or(a), seconds), k + 1, end));
+    }
+    // Numbers and ranges, ex: 1 1/2, 25-30, 25 to 30
+    let mut j = k;
+    while let Some((_, t)) = tokens.get(j) {
+        let joins = j > k && ["to", "or", "-", "–"].contains(&t.to_lowercase().as_str());
+        if is_number(t) || (joins && tokens.get(j + 1).is_some_and(|(_, n)| is_number(n))) {
+            j += 1;
+        } else {
+            break;
+        }
+    }
+    let &(unit_start, unit) = tokens.get(j)?;
+    let seconds = time_unit(unit)?;
+    let (last_start, last) = tokens[j - 1];
+    let (a, b) = MixedRational::try_from_string(&text[start..last_start + last.len()]).ok()?;
+    let end = unit_start + unit.trim_end_matches([',', '.', ';', ':', ')', '!']).len();
+    Some((to_duration(a, seconds), to_duration(b.unwrap_or(a), seconds), j + 1, end))
+}
+
+// The closest timer verb before `at` in the same sentence
+fn label_before(text: &str, at: usize) -> Option<String> {
+    let before = text[..at].to_lowercase();
+    let sentence = before.rfind(['.', ';', '!']).map_or(0, |i| i + 1);
+    before[sentence..].split_whitespace().rev().find_map(|word| {
+        let word = word.trim_matches(|c: char| !c.is_alphabetic());
+        TIMER_VERBS
+            .iter()
+            .find(|v| {
+                let stem = v.strip_suffix('e').unwrap_or(v);
+                [v.to_string(), format!("{}s", v), format!("{}d", v), format!("{}ed", v), format!("{}ing", stem)]
+                    .contains(&word.to_string())
+            })
+            .map(|v| v.to_string())
+    })
+}
+
+/// Every duration in a step, times written together like "1 hour 15 minutes" are one timer
+pub fn find_timers(text: &str) -> Vec<Timer> {
+    let tokens = text
+        .split_whitespace()
+        .map(|t| {
+            let trimmed = t.trim_start_matches(['(', '~']);
+            (trimmed.as_ptr() as usize - text.as_ptr() as usize, trimmed)
+        })
+        .collect::<Vec<_>>();
+    let mut timers = Vec::new();
+    let mut k = 0;
+    while k < tokens.len() {
+        let Some((mut min, mut max, mut next, mut end)) = read_time(text, &tokens, k) else {
+            k += 1;
+            continue;
+        };
+        // Smaller units right after, unless punctuation splits them
+        while !text[..end].ends_with([',', '.', ';', ':', ')']) && text[end..].starts_with(' ') {
+            let j = next + tokens.get(next).is_some_and(|(_, t)| t.eq_ignore_ascii_case("and")) as usize;
+            match read_time(text, &tokens, j) {
+                Some((a, b, n, e)) if a < min => {
+                    (min, max, next, end) = (min + a, max + b, n, e);
+                }
+                _ => break,
+            }
+        }
+        let start = tokens[k].0;
+        timers.push(Timer {
+            min,
+            max,
+            label: label_before(text, start),
+            text: text[start..end].to_string(),
+        });
+        k = next;
+    }
+    timers
+}
+
+impl RecipeData {
+    /// Shortest and longest time the directions take if followed in order, steps starting with
+    /// "meanwhile" or "while" happen during the step before them
+    pub fn critical_path(&self) -> (Duration, Duration) {
+        let (mut total, mut current) = ((Duration::ZERO, Duration::ZERO), (Duration::ZERO, Duration::ZERO));
+        for step in self.directions.iter().flat_map(|s| s.sections.iter()) {
+            let time = step
+                .timers
+                .iter()
+                .fold((Duration::ZERO, Duration::ZERO), |(a, b), t| (a + t.min, b + t.max));
+            let lower = step.text.trim_start().to_lowercase();
+            if PARALLEL_MARKERS.iter().any(|m| lower.starts_with(m)) {
+                current = (current.0.max(time.0), current.1.max(time.1));
+            } else {
+                total = (total.0 + current.0, total.1 + current.1);
+                current = time;
+            }
+        }
+        (total.0 + current.0, total.1 + current.1)
+    }
+}
@@ -0,0 +1,357 @@
+use serde::{de::Visitor, ser::SerializeMap};
+use std::{
+    fmt::{self, Display},
+    time::Duration,
+};
+
+/// How long a recipe takes, every time is optional as sources rarely give all of them
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RecipeTimes {
+    pub prep: Option<Duration>,
+    pub cook: Option<Duration>,
+    pub total: Option<Duration>,
+    /// Hands on time
+    pub active: Option<Duration>,
+    /// Waiting on something, ex: rising dough, marinating
+    pub resting: Option<Duration>,
+}
+
+impl RecipeTimes {
+    /// Read schema.org `prepTime`, `cookTime`, `performTime` and `totalTime`, anything unreadable is skipped
+    pub fn from_json_ld(value: &serde_json::Value) -> Self {
+        let get = |key: &str| value[key].as_str().and_then(|s| parse_duration(s).ok());
+        RecipeTimes {
+            prep: get("prepTime"),
+            cook: get("cookTime").or_else(|| get("performTime")),
+            total: get("totalTime"),
+            ..Default::default()
+        }
+    }
+    /// The stated total, or everything that adds up to it when there isn't one
+    pub fn total_or_sum(&self) -> Option<Duration> {
+        self.total.or_else(|| {
+            [self.prep, self.cook, self.resting]
+                .iter()
+                .flatten()
+                .copied()
+                .reduce(|a, b| a + b)
+        })
+    }
+    pub fn is_empty(&self) -> bool {
+        self.named().all(|(_, d)| d.is_none())
+    }
+    /// Each time with its json key
+    pub fn named(&self) -> impl Iterator<Item = (&'static str, Option<Duration>)> {
+        [
+            ("prep", self.prep),
+            ("cook", self.cook),
+            ("total", self.total),
+            ("active", self.active),
+            ("resting", self.resting),
+        ]
+        .into_iter()
+    }
+}
+
+impl Display for RecipeTimes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let times = self
+            .named()
+            .filter_map(|(name, d)| d.map(|d| format!("{}: {}", name, format_duration(d))))
+            .collect::<Vec<_>>();
+        write!(f, "{}", times.join(", "))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseDurationErrorKind {
+    Empty,
+    /// ISO-8601 durations start with P
+    MissingPeriod,
+    InvalidNumber,
+    UnexpectedToken,
+    /// Years and months don't have a fixed length
+    AmbiguousUnit,
+    /// Longer than a `Duration` can hold, ex: "P99999999999999999999W"
+    TooLong,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDurationError {
+    /// Byte offset of the offending token in the parsed string
+    pub position: usize,
+    pub token: String,
+    pub reason: ParseDurationErrorKind,
+}
+
+impl ParseDurationError {
+    fn new(position: usize, token: &str, reason: ParseDurationErrorKind) -> Self {
+        ParseDurationError {
+            position,
+            token: token.into(),
+            reason,
+        }
+    }
+}
+
+impl Display for ParseDurationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.reason {
+            ParseDurationErrorKind::Empty => "expected a duration",
+            ParseDurationErrorKind::MissingPeriod => "expected a duration starting with P",
+            ParseDurationErrorKind::InvalidNumber => "invalid number",
+            ParseDurationErrorKind::UnexpectedToken => "unexpected token",
+            ParseDurationErrorKind::AmbiguousUnit => "years and months have no fixed length",
+            ParseDurationErrorKind::TooLong => "duration too long",
+        };
+        write!(f, "{} at {}: \"{}\"", reason, self.position, self.token)
+    }
+}
+
+impl std::error::Error for ParseDurationError {}
+
+/// Parses an ISO-8601 duration such as "PT1H30M", "P1DT2H" or "PT0.5H"
+pub fn parse_iso8601_duration(s: &str) -> Result<Duration, ParseDurationError> {
+    use ParseDurationErrorKind::*;
+    let s = s.trim();
+    if s.is_empty() {
+        return Err(ParseDurationError::new(0, s, Empty));
+    }
+    if !s.starts_with(['P', 'p']) {
+        return Err(ParseDurationError::new(0, s, MissingPeriod));
+    }
+    let (mut seconds, mut in_time, mut number_start) = (0f64, false, None);
+    for (i, c) in s.char_indices().skip(1) {
+        let c = c.to_ascii_uppercase();
+        if c.is_ascii_digit() || c == '.' || c == ',' {
+            number_start.get_or_insert(i);
+            continue;
+        }
+        if c == 'T' && number_start.is_none() && !in_time {
+            in_time = true;
+            continue;
+        }
+        let Some(start) = number_start.take() else {
+            return Err(ParseDurationError::new(i, &s[i..i + c.len_utf8()], UnexpectedToken));
+        };
+        let token = &s[start..i];
+        let n = token
+            .replace(',', ".")
+            .parse::<f64>()
+            .map_err(|_| ParseDurationError::new(start, token, InvalidNumber))?;
+        let unit = match (in_time, c) {
+            (false, 'W') => 604800.,
+            (false, 'D') => 86400.,
+            (true, 'H') => 3600.,
+            (true, 'M') => 60.,
+            (true, 'S') => 1.,
+            (false, 'Y' | 'M') if n == 0. => 0.,
+            (false, 'Y' | 'M') => return Err(ParseDurationError::new(start, &s[start..=i], AmbiguousUnit)),
+            _ => return Err(ParseDurationError::new(i, &s[i..i + c.len_utf8()], UnexpectedToken)),
+        };
+        seconds += n * unit;
+    }
+    if let Some(start) = number_start {
+        return Err(ParseDurationError::new(start, &s[start..], UnexpectedToken));
+    }
+    if s.len() == 1 || s.to_ascii_uppercase().ends_with('T') {
+        return Err(ParseDurationError::new(s.len(), "", Empty));
+    }
+    Duration::try_from_secs_f64(seconds).map_err(|_| ParseDurationError::new(0, s, TooLong))
+}
+
+/// Parses what people write, ex: "1 hour 30 minutes", "1h 15m", "90 mins"
+pub fn parse_human_duration(s: &str) -> Result<Duration, ParseDurationError> {
+    use ParseDurationErrorKind::*;
+    let lower = s.to_lowercase();
+    let mut seconds = 0f64;
+    let mut number: Option<(usize, f64)> = None;
+    let mut found = false;
+    let mut i = 0;
+    while i < lower.len() {
+        let rest = &lower[i..];
+        let len = if rest.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+            rest.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(rest.len())
+        } else if rest.starts_with(char::is_alphabetic) {
+            rest.find(|c: char| !c.is_alphabetic()).unwrap_or(rest.len())
+        } else {
+            rest.chars().next().map_or(1, char::len_utf8)
+        };
+        let token = &rest[..len];
+        if token.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+            let n = token
+                .parse::<f64>()
+                .map_err(|_| ParseDurationError::new(i, token, InvalidNumber))?;
+            number = Some((i, n));
+        } else if token.starts_with(char::is_alphabetic) {
+            let unit = match token {
+                "d" | "day" | "days" => 86400.,
+                "h" | "hr" | "hrs" | "hour" | "hours" => 3600.,
+                "m" | "min" | "mins" | "minute" | "minutes" => 60.,
+                "s" | "sec" | "secs" | "second" | "seconds" => 1.,
+                "and" => 0.,
+                _ => return Err(ParseDurationError::new(i, token, UnexpectedToken)),
+            };
+            if unit != 0. {
+                let Some((_, n)) = number.take() else {
+                    return Err(ParseDurationError::new(i, token, UnexpectedToken));
+                };
+                seconds += n * unit;
+                found = true;
+            }
+        }
+        i += len;
+    }
+    if let Some((pos, _)) = number {
+        return Err(ParseDurationError::new(pos, &lower[pos..], UnexpectedToken));
+    }
+    if !found {
+        return Err(ParseDurationError::new(0, s, Empty));
+    }
+    Duration::try_from_secs_f64(seconds).map_err(|_| ParseDurationError::new(0, s, TooLong))
+}
+
+/// ISO-8601 when it starts with P, otherwise what people write
+pub fn parse_duration(s: &str) -> Result<Duration, ParseDurationError> {
+    if s.trim().starts_with(['P', 'p']) {
+        parse_iso8601_duration(s)
+    } else {
+        parse_human_duration(s)
+    }
+}
+
+/// ex: 1 hour 30 minutes
+pub fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    let parts = [
+        (secs / 86400, "day"),
+        (secs % 86400 / 3600, "hour"),
+        (secs % 3600 / 60, "minute"),
+        (secs % 60, "second"),
+    ];
+    let s = parts
+        .iter()
+        .filter(|(n, _)| *n != 0)
+        .map(|(n, unit)| format!("{} {}{}", n, unit, if *n == 1 { "" } else { "s" }))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if s.is_empty() {
+        "0 minutes".into()
+    } else {
+        s
+    }
+}
+
+/// ex: PT1H30M
+pub fn to_iso8601(d: Duration) -> String {
+    let secs = d.as_secs();
+    let (days, hours, minutes, seconds) = (secs / 86400, secs % 86400 / 3600, secs % 3600 / 60, secs % 60);
+    let mut s = String::from("P");
+    if days != 0 {
+        s.push_str(&format!("{}D", days));
+    }
+    if hours != 0 || minutes != 0 || seconds != 0 || days == 0 {
+        s.push('T');
+    }
+    for (n, unit) in [(hours, 'H'), (minutes, 'M'), (seconds, 'S')] {
+        if n != 0 {
+            s.push_str(&format!("{}{}", n, unit));
+        }
+    }
+    if s == "PT" {
+        s.push_str("0S");
+    }
+    s
+}
+
+impl serde::Serialize for RecipeTimes {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let times = self.named().filter_map(|(name, d)| d.map(|d| (name, d))).collect::<Vec<_>>();
+        let mut map = serializer.serialize_map(Some(times.len()))?;
+        for (name, d) in times {
+            map.serialize_entry(name, &to_iso8601(d))?;
+        }
+        map.end()
+    }
+}
+
+pub struct RecipeTimesVisitor;
+impl<'de> Visitor<'de> for RecipeTimesVisitor {
+    type Value = RecipeTimes;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "A map of ISO-8601 durations or a single written time")
+    }
+
+    // Recipes saved before `RecipeTimes` have a single free text time, which was the total
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(RecipeTimes {
+            total: parse_duration(v).ok(),
+            ..Default::default()
+        })
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        let mut times = RecipeTimes::default();
+        while let Some((key, value)) = map.next_entry::<String, String>()? {
+            let d = Some(parse_duration(&value).map_err(serde::de::Error::custom)?);
+            match key.as_str() {
+                "prep" => times.prep = d,
+                "cook" => times.cook = d,
+                "total" => times.total = d,
+                "active" => times.active = d,
+                "resting" => times.resting = d,
+                _ => {}
+            }
+        }
+        Ok(times)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for RecipeTimes {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(RecipeTimesVisitor)
+    }
+}
+
+/// Serde helper writing a `Duration` as ISO-8601
+pub fn serialize_iso8601<S>(d: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.serialize_str(&to_iso8601(*d))
+}
+
+/// Serde helper reading a `Duration` from ISO-8601 or written text
+pub fn deserialize_iso8601<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let s = <String as serde::Deserialize>::deserialize(deserializer)?;
+    parse_duration(&s).map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overflowing_durations_are_errors() {
+        for s in ["P99999999999999999999W", "PT99999999999999999999H", "99999999999999999999 hours"] {
+            assert_eq!(parse_duration(s).unwrap_err().reason, ParseDurationErrorKind::TooLong, "{}", s);
+        }
+        assert_eq!(parse_duration("PT1H30M"), Ok(Duration::from_secs(5400)));
+    }
+}
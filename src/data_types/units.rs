@@ -1,4 +1,8 @@
 use serde::de::Visitor;
+use std::{
+    collections::HashMap,
+    sync::{OnceLock, RwLock},
+};
 
 use crate::MixedRational;
 
@@ -11,15 +15,60 @@ Uk,
 None,
 }*/
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum UnitType {
     Imperial,
     Metric,
+    // Metric, but with a 20ml tablespoon
+    Australian,
 
     UsCustomary,
     UsLegal,
 
     Traditional,
 }
+
+impl UnitType {
+    /// Guess where a recipe is from by the top level domain of its url
+    pub fn from_origin(url: &str) -> Option<Self> {
+        let host = url.split("://").last()?.split(['/', ':']).next()?;
+        let tld = host.rsplit('.').next()?;
+        match tld {
+            "uk" | "ie" => Some(UnitType::Imperial),
+            "au" => Some(UnitType::Australian),
+            "nz" | "ca" | "za" | "de" | "fr" | "nl" | "se" | "dk" | "no" | "fi" | "it" | "es" => {
+                Some(UnitType::Metric)
+            }
+            "us" => Some(UnitType::UsCustomary),
+            _ => None,
+        }
+    }
+    /// Units of `self` that are also fine to use when converting into `other`
+    pub fn includes(&self, other: UnitType) -> bool {
+        *self == other || (*self == UnitType::Australian && other == UnitType::Metric)
+    }
+    /// The regional version of a unit written in a recipe from this system, ex: an
+    /// Australian "tablespoon" is 20ml
+    pub fn resolve(&self, unit: Unit) -> Unit {
+        use Unit::*;
+        match (self, unit) {
+            (UnitType::Imperial, FluidOunce) => ImperialFluidOunce,
+            (UnitType::Imperial, Gill) => ImperialGill,
+            (UnitType::Imperial, Pint) => ImperialPint,
+            (UnitType::Imperial, Quart) => ImperialQuart,
+            (UnitType::Imperial, Gallon) => ImperialGallon,
+            (UnitType::UsLegal, Cup) => LegalCup,
+            (UnitType::UsLegal, FluidOunce) => LegalFluidOunce,
+            (UnitType::Australian, Tablespoon) => AustralianTablespoon,
+            (UnitType::Imperial | UnitType::Metric | UnitType::Australian, Cup) => MetricCup,
+            (UnitType::Imperial | UnitType::Metric | UnitType::UsLegal, Tablespoon) => MetricTablespoon,
+            (UnitType::Imperial | UnitType::Metric | UnitType::Australian | UnitType::UsLegal, Teaspoon) => {
+                MetricTeaspoon
+            }
+            _ => unit,
+        }
+    }
+}
 #[derive(Clone, Copy, Debug, Default, serde::Serialize, serde::Deserialize)]
 pub struct MeasureType {
     pub count: MixedRational,
@@ -29,18 +78,34 @@ pub struct MeasureType {
 impl std::ops::Mul<MixedRational> for MeasureType {
     type Output = MeasureType;
     fn mul(self, rhs: MixedRational) -> Self::Output {
+        // Regional units only ever turn into units of their own system
+        let unit = self.unit;
+        // Counted units stay counted, 3 sticks of butter shouldn't become 1 1/2 cups
+        if unit.unit.is_count() || unit.unit == Unit::Custom {
+            return MeasureType {
+                count: self.count * rhs,
+                unit,
+            };
+        }
+        // Metric units stay metric, half of 1.5 kg is 750 g rather than 1 5/8 lb, and deciliters only
+        // come from recipes that use them
+        let same_region = move |m: &Measure| {
+            (m.unit.is_decimal() || !unit.unit.is_decimal())
+                && (m.unit != Unit::Deciliter || unit.unit == Unit::Deciliter)
+                && (m.system == unit.system || !(m.unit.is_regional() || unit.unit.is_regional()))
+        };
         if self.unit.fluid {
             MeasureType {
                 count: self.count * rhs,
                 unit: self.unit,
             }
-            .simplify(|m| m.fluid && m.unit as u8 != Unit::Gill as u8)
+            .simplify(|m| m.fluid && m.unit as u8 != Unit::Gill as u8 && same_region(m))
         } else {
             MeasureType {
                 count: self.count * rhs,
                 unit: self.unit,
             }
-            .simplify(|m| !m.fluid && m.unit as u8 != Unit::Gill as u8)
+            .simplify(|m| !m.fluid && m.unit as u8 != Unit::Gill as u8 && same_region(m))
         }
     }
 }
@@ -56,6 +121,16 @@ impl MeasureType {
                 unit: new_unit,
             })
     }
+    /// Like `convert`, but can switch between volume and mass given grams per milliliter
+    pub fn convert_with_density(&self, new_unit: Unit, density: Option<MixedRational>) -> Option<Self> {
+        let new_unit = Measure::from_enum(new_unit);
+        self.unit
+            .convert_universal(self.count, new_unit, density)
+            .map(|new_count| MeasureType {
+                count: new_count,
+                unit: new_unit,
+            })
+    }
     pub fn new(name: String, count: MixedRational) -> Self {
         MeasureType {
             count,
@@ -65,37 +140,147 @@ impl MeasureType {
     pub fn memory_size(&self) -> usize {
         std::mem::size_of_val(&self.count) + std::mem::size_of_val(&self.unit)
     }
-    pub fn simplify(&self, filter: fn(&Measure) -> bool) -> Self {
+    /// Convert into the nicest unit of `system`, measures without a system are left as is
+    pub fn to_unit_system(&self, system: UnitType) -> Self {
+        if system.includes(self.unit.system) || self.unit.system == UnitType::Traditional {
+            return *self;
+        }
+        let (volume, mass) = (self.unit.is_volume(), self.unit.is_mass());
+        self.simplify(|m| {
+            system.includes(m.system)
+                && m.is_volume() == volume
+                && m.is_mass() == mass
+                && m.unit != Unit::Gill
+                && m.unit.is_metric_target()
+        })
+    }
+    /// Snap to the measuring tools in `rounding`, one part per tool size used, ex: 5/8 cup is
+    /// 1/2 cup + 2 tbsp
+    pub fn round_for_kitchen(&self, rounding: &KitchenRounding) -> RoundedMeasure {
+        let unrounded = RoundedMeasure {
+            parts: vec![*self],
+            error: MeasureType { count: MixedRational::default(), unit: self.unit },
+        };
+        let tolerance = self.count.abs() * rounding.tolerance;
+        let mut remaining = self.count;
+        let mut parts = Vec::new();
+        let mut last = None;
+        for unit in rounding.units.iter() {
+            let measure = Measure::from_enum(*unit);
+            let (Some(r), Some(tol)) = (
+                self.unit.convert(remaining, measure),
+                self.unit.convert(tolerance, measure),
+            ) else {
+                continue;
+            };
+            let mut sizes = get_standard_size(*unit);
+            sizes.sort();
+            let (used, done) = Self::fill_with_sizes(r, tol, &sizes);
+            for amount in used {
+                parts.push(MeasureType { count: amount, unit: measure });
+                remaining -= measure.convert(amount, self.unit).unwrap_or(amount);
+            }
+            if done {
+                last = None;
+                break;
+            }
+            last = sizes.first().map(|s| (measure, *s));
+        }
+        // Nothing fit within the tolerance, round the smallest tool to the nearest size
+        if let Some((measure, smallest)) = last {
+            let r = self.unit.convert(remaining, measure).unwrap_or(remaining);
+            if r * MixedRational::whole(2) >= smallest {
+                let of_smallest = |p: &MeasureType| {
+                    let times = p.count / smallest;
+                    p.unit.unit == measure.unit && times.floor() == times
+                };
+                match parts.last_mut() {
+                    Some(p) if of_smallest(p) => p.count += smallest,
+                    _ => parts.push(MeasureType { count: smallest, unit: measure }),
+                }
+                remaining -= measure.convert(smallest, self.unit).unwrap_or(smallest);
+            }
+        }
+        let Some(finest) = parts.last().map(|p| p.unit) else {
+            return unrounded;
+        };
+        // How far off it is in the smallest tool used, ex: 1/8 tsp over
+        let error = self.unit.convert(-remaining, finest).unwrap_or(-remaining);
+        RoundedMeasure {
+            parts,
+            error: MeasureType { count: round_to_unit(error, finest.unit), unit: finest },
+        }
+    }
+    /// Greedily fill `amount` with `sizes` (ascending), returns the amount of each size used, largest
+    /// first, and whether the leftover is within `tolerance`
+    fn fill_with_sizes(
+        amount: MixedRational,
+        tolerance: MixedRational,
+        sizes: &[MixedRational],
+    ) -> (Vec<MixedRational>, bool) {
+        let (mut used, mut total) = (Vec::new(), MixedRational::default());
+        for size in sizes.iter().rev() {
+            let times = ((amount - total) / *size).floor();
+            let took = times >= MixedRational::whole(1);
+            if took {
+                used.push(*size * times);
+                total += *size * times;
+            }
+            if amount - total <= tolerance {
+                return (used, true);
+            }
+            if total + *size - amount <= tolerance {
+                match used.last_mut() {
+                    Some(u) if took => *u += *size,
+                    _ => used.push(*size),
+                }
+                return (used, true);
+            }
+        }
+        (used, false)
+    }
+    pub fn simplify<F>(&self, filter: F) -> Self
+    where
+        F: Fn(&Measure) -> bool,
+    {
         let unit = self.unit;
+        let convert = |m: Measure| {
+            let exact = unit.convert(self.count, m)?;
+            if m.unit.is_decimal() {
+                rounded_decimal(exact)
+            } else {
+                readable(exact)
+            }
+        };
         let test = STANDARD_COOKING_MEASUREMENT_UNITS
             .iter()
-            .filter(|f| filter(f) && (f.unit as u8 != Unit::Other as u8))
+            .filter(|f| filter(f) && (f.unit as u8 != Unit::Other as u8) && !f.unit.is_count())
             .min_by_key(|x| {
-                if let Some(r) = unit.convert(self.count, **x) {
+                if let Some(r) = convert(**x) {
                     let (val, den, num) = (
-                        (r.value as f64).log10().ceil() as i32,
-                        (r.den as f64).log10().ceil() as i32,
-                        (r.num as f64).log10().ceil() as i32,
+                        (r.value as f64).log10().ceil() as i64,
+                        (r.den as f64).log10().ceil() as i64,
+                        (r.num as f64).log10().ceil() as i64,
                     );
 
-                    let add = (r >= self.count) as i32
+                    let add = (r >= self.count) as i64
                         + if (val > 2) { val } else { 0 }
                         + if (num > 2) { num } else { 0 }
                         + if (den > 2) { den } else { 0 };
 
                     (if r.value != 0 && r.den != 0 {
-                        r.value * r.num + r.den as i32
+                        r.value.saturating_mul(r.num).saturating_add(r.den as i64)
                     } else if r.den != 0 {
-                        r.num + r.den as i32
+                        r.num.saturating_add(r.den as i64)
                     } else {
                         r.value
-                    }) + add
+                    }).saturating_add(add)
                 } else {
-                    i32::MAX
+                    i64::MAX
                 }
             });
         let (new_count, new_unit) = if let Some(conversion) = test {
-            if let Some(c) = unit.convert(self.count, *conversion) {
+            if let Some(c) = convert(*conversion) {
                 (c, *conversion)
             } else {
                 (self.count, self.unit)
@@ -115,6 +300,7 @@ pub struct Measure {
     pub unit: Unit,
     pub names: &'static [&'static str],
     pub fluid: bool,
+    pub system: UnitType,
     // ,
 }
 
@@ -124,6 +310,7 @@ impl Default for Measure {
             unit: Unit::Other,
             names: &[""],
             fluid: false,
+            system: UnitType::Traditional,
         }
     }
 }
@@ -168,7 +355,7 @@ impl std::fmt::Display for Measure {
     }
 }
 
-#[derive(Clone, Copy, Debug, serde::Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize)]
 pub enum Unit {
     // Spices
     Drop,
@@ -201,9 +388,240 @@ pub enum Unit {
     Gallon,
     Milligram,
 
+    // Regional variants
+    ImperialFluidOunce,
+    ImperialGill,
+    ImperialPint,
+    ImperialQuart,
+    ImperialGallon,
+    LegalCup,
+    LegalFluidOunce,
+    MetricCup,
+    MetricTeaspoon,
+    MetricTablespoon,
+    AustralianTablespoon,
+
+    // Counts and containers
+    Can,
+    Jar,
+    Bottle,
+    Package,
+    Box,
+    Bag,
+    Stick,
+    Clove,
+    Bunch,
+    Head,
+    Sprig,
+    Slice,
+    Piece,
+    /// One whole recipe, for recipes used in others, ex: 1 batch pie crust
+    Batch,
+
+    // Temperature
+    Fahrenheit,
+    Celsius,
+    GasMark,
+
+    /// Registered at runtime by name, see `ConversionGraph::register`
+    Custom,
     Other,
 }
 
+impl Unit {
+    pub fn is_regional(&self) -> bool {
+        use Unit::*;
+        matches!(
+            self,
+            ImperialFluidOunce | ImperialGill | ImperialPint | ImperialQuart | ImperialGallon
+                | LegalCup | LegalFluidOunce | MetricCup | MetricTeaspoon | MetricTablespoon
+                | AustralianTablespoon
+        )
+    }
+    /// Units counted out rather than measured, ex: 2 cans, 3 cloves
+    pub fn is_count(&self) -> bool {
+        use Unit::*;
+        matches!(
+            self,
+            Can | Jar | Bottle | Package | Box | Bag | Stick | Clove | Bunch | Head | Sprig | Slice | Piece | Batch
+        )
+    }
+    pub fn is_temperature(&self) -> bool {
+        matches!(self, Unit::Fahrenheit | Unit::Celsius | Unit::GasMark)
+    }
+    /// Count units that hold an amount of something, ex: a 14.5 oz can
+    pub fn is_container(&self) -> bool {
+        use Unit::*;
+        matches!(self, Can | Jar | Bottle | Package | Box | Bag)
+    }
+    /// Metric units written as decimals rather than fractions, ex: 454 g, 1.5 l
+    pub fn is_decimal(&self) -> bool {
+        use Unit::*;
+        matches!(self, Milliliter | Deciliter | Liter | Milligram | Gram | Kilogram)
+    }
+    /// Whether amounts may be converted into this unit, metric is only ever ml, l, g, kg and mg,
+    /// never deciliters or metric cups and spoons
+    pub fn is_metric_target(&self) -> bool {
+        use Unit::*;
+        !matches!(self, Deciliter | MetricCup | MetricTeaspoon | MetricTablespoon)
+    }
+    /// Exact size of mass units in milligrams
+    pub fn milligrams(&self) -> Option<MixedRational> {
+        use Unit::*;
+        // 1 pound is 453.59237g
+        let pound = MixedRational::fract(45359237, 100);
+        match self {
+            Milligram => Some(MixedRational::whole(1)),
+            Gram => Some(MixedRational::whole(1000)),
+            Kilogram => Some(MixedRational::whole(1000000)),
+            Ounce => Some(pound / MixedRational::whole(16)),
+            Pound => Some(pound),
+            _ => None,
+        }
+    }
+    /// Exact size of volume units in milliliters
+    pub fn milliliters(&self) -> Option<MixedRational> {
+        use Unit::*;
+        // 1 US pint is 473.176473ml, 1 imperial gallon is 4546.09ml
+        let (us, imperial) = (MixedRational::fract(473176473, 1000000), MixedRational::fract(454609, 100));
+        match self {
+            Milliliter => Some(MixedRational::whole(1)),
+            Deciliter => Some(MixedRational::whole(100)),
+            Liter => Some(MixedRational::whole(1000)),
+            Teaspoon => Some(us / MixedRational::whole(96)),
+            Tablespoon => Some(us / MixedRational::whole(32)),
+            FluidOunce => Some(us / MixedRational::whole(16)),
+            Gill => Some(us / MixedRational::whole(4)),
+            Cup => Some(us / MixedRational::whole(2)),
+            Pint => Some(us),
+            Quart => Some(us * 2),
+            Gallon => Some(us * 8),
+            ImperialFluidOunce => Some(imperial / MixedRational::whole(160)),
+            ImperialGill => Some(imperial / MixedRational::whole(32)),
+            ImperialPint => Some(imperial / MixedRational::whole(8)),
+            ImperialQuart => Some(imperial / MixedRational::whole(4)),
+            ImperialGallon => Some(imperial),
+            LegalCup => Some(MixedRational::whole(240)),
+            LegalFluidOunce => Some(MixedRational::whole(30)),
+            MetricCup => Some(MixedRational::whole(250)),
+            MetricTeaspoon => Some(MixedRational::whole(5)),
+            MetricTablespoon => Some(MixedRational::whole(15)),
+            AustralianTablespoon => Some(MixedRational::whole(20)),
+            // A stick of butter is half a cup
+            Stick => Some(us / MixedRational::whole(4)),
+            _ => None,
+        }
+    }
+}
+
+/// Largest denominator `MeasureType::simplify` will produce
+pub const MAX_DENOMINATOR: u64 = 100;
+
+/// Conversions between systems are exact, so swap huge fractions for kitchen
+/// friendly ones (within 2%) or at least small ones (within 1%)
+fn readable(exact: MixedRational) -> Option<MixedRational> {
+    if exact.den <= MAX_DENOMINATOR {
+        return Some(exact);
+    }
+    [(8, 50), (MAX_DENOMINATOR, 100)].iter().find_map(|(den, tolerance)| {
+        let approx = exact.limit_denominator(*den);
+        let error = (approx - exact).abs() * MixedRational::whole(*tolerance);
+        (!approx.is_zero() && error <= exact.abs()).then_some(approx)
+    })
+}
+
+/// Whole numbers from 10 up and tenths below, None under 1 so a smaller unit is used, ex: 454 g
+/// rather than 0.45 kg
+fn rounded_decimal(exact: MixedRational) -> Option<MixedRational> {
+    let rounded = round_decimal(exact);
+    (rounded.abs() >= MixedRational::whole(1)).then_some(rounded)
+}
+
+fn round_decimal(exact: MixedRational) -> MixedRational {
+    let step = if exact.abs() >= MixedRational::whole(10) { 1 } else { 10 };
+    (exact * step + MixedRational::fract(1, 2)).floor() / MixedRational::whole(step as i64)
+}
+
+/// Round an amount converted into `unit` the way it's written, ex: 125 g, 1.5 l, 2/3 cup
+pub fn round_to_unit(exact: MixedRational, unit: Unit) -> MixedRational {
+    match round_decimal(exact) {
+        rounded if unit.is_decimal() && !rounded.is_zero() => rounded,
+        _ => exact.limit_denominator(MAX_DENOMINATOR),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Dimension {
+    /// Measured in milliliters
+    Volume,
+    /// Measured in milligrams
+    Mass,
+}
+
+/// Every unit is stored as an amount of its dimension's base unit, so converting
+/// between any two units of the same dimension is a single division
+#[derive(Clone, Debug, Default)]
+pub struct ConversionGraph {
+    /// Keyed by the unit's first name, ex: "cup"
+    pub units: HashMap<&'static str, (Dimension, MixedRational)>,
+    /// Units registered by name that aren't in `STANDARD_COOKING_MEASUREMENT_UNITS`
+    pub custom: Vec<Measure>,
+}
+
+impl ConversionGraph {
+    pub fn standard() -> Self {
+        let mut graph = ConversionGraph::default();
+        for m in STANDARD_COOKING_MEASUREMENT_UNITS {
+            if let Some(ml) = m.unit.milliliters() {
+                graph.units.insert(m.names[0], (Dimension::Volume, ml));
+            } else if let Some(mg) = m.unit.milligrams() {
+                graph.units.insert(m.names[0], (Dimension::Mass, mg));
+            }
+        }
+        graph
+    }
+    /// The graph used by `Measure::convert`, new units can be registered into it
+    pub fn global() -> &'static RwLock<ConversionGraph> {
+        static GRAPH: OnceLock<RwLock<ConversionGraph>> = OnceLock::new();
+        GRAPH.get_or_init(|| RwLock::new(ConversionGraph::standard()))
+    }
+    /// Give the unit called `name` a size, ex: a can is 400 ml, or add it if there's no such unit,
+    /// ex: a knob of butter is 15 g. `base_amount` is in milliliters for volume and milligrams for mass
+    pub fn register(&mut self, name: &str, dimension: Dimension, base_amount: MixedRational) -> Measure {
+        let name = name.trim();
+        let measure = Measure::find(name, STANDARD_COOKING_MEASUREMENT_UNITS)
+            .or_else(|| Measure::find(name, &self.custom))
+            .unwrap_or_else(|| {
+                // Measures are Copy with static names, a new unit's name lives as long as the program
+                let names: &'static [&'static str] = Box::leak(Box::new([&*Box::leak(name.into())]));
+                let measure = Measure {
+                    unit: Unit::Custom,
+                    names,
+                    fluid: dimension == Dimension::Volume,
+                    system: UnitType::Traditional,
+                };
+                self.custom.push(measure);
+                measure
+            });
+        self.units.insert(measure.names[0], (dimension, base_amount));
+        measure
+    }
+    pub fn dimension(&self, measure: &Measure) -> Option<Dimension> {
+        self.units.get(measure.names[0]).map(|(d, _)| *d)
+    }
+    /// How many of `a` are in one `b`, None if they measure different things
+    pub fn factor(&self, a: &Measure, b: &Measure) -> Option<MixedRational> {
+        if a.names[0] == b.names[0] {
+            return Some(MixedRational::whole(1));
+        }
+        let ((dim_a, base_a), (dim_b, base_b)) = (self.units.get(a.names[0])?, self.units.get(b.names[0])?);
+        if dim_a != dim_b || base_a.is_zero() {
+            return None;
+        }
+        Some(*base_b / *base_a)
+    }
+}
+
 impl serde::Serialize for Unit {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -224,12 +642,21 @@ pub fn min_measurements(measure: MeasureType) -> Vec<MeasureType> {
 
 pub fn get_standard_size(unit: Unit) -> Vec<MixedRational> {
     match unit {
-        Unit::Cup => {
-            MixedRational::from_frac_list(vec![[1, 8], [1, 4], [1, 3], [1, 2], [3, 4], [1, 1]])
+        // Eighths of a cup are measured as 2 tbsp
+        Unit::Cup | Unit::MetricCup | Unit::LegalCup => {
+            MixedRational::from_frac_list(vec![[1, 4], [1, 3], [1, 2], [3, 4], [1, 1]])
+        }
+        Unit::Tablespoon | Unit::MetricTablespoon | Unit::AustralianTablespoon => {
+            MixedRational::from_frac_list(vec![[1, 2], [1, 1]])
         }
-        Unit::Teaspoon => {
+        Unit::Teaspoon | Unit::MetricTeaspoon => {
             MixedRational::from_frac_list(vec![[1, 8], [1, 4], [1, 3], [1, 2], [3, 4], [1, 1]])
         }
+        // Metric spoons (1.25, 2.5, 5, 15ml) and cups (60, 80, 125, 250ml)
+        Unit::Milliliter => MixedRational::from_frac_list(vec![
+            [5, 4], [5, 2], [5, 1], [15, 1], [60, 1], [80, 1], [125, 1], [250, 1],
+        ]),
+        Unit::Liter => MixedRational::from_frac_list(vec![[1, 2], [1, 1]]),
         Unit::Drop => MixedRational::from_frac_list(vec![[1, 1]]),
         Unit::Smidgen => MixedRational::from_frac_list(vec![[1, 1]]),
         Unit::Pinch => MixedRational::from_frac_list(vec![[1, 1]]),
@@ -239,194 +666,171 @@ pub fn get_standard_size(unit: Unit) -> Vec<MixedRational> {
     }
 }
 
+/// Which measuring tools may be used when rounding, and how far off the result may be
+#[derive(Clone, Debug)]
+pub struct KitchenRounding {
+    /// Units whose standard sizes are in the drawer, largest first
+    pub units: Vec<Unit>,
+    /// Allowed error as a fraction of the original quantity
+    pub tolerance: MixedRational,
+}
+
+impl KitchenRounding {
+    pub fn us() -> Self {
+        KitchenRounding {
+            units: vec![Unit::Cup, Unit::Tablespoon, Unit::Teaspoon],
+            tolerance: MixedRational::fract(1, 20),
+        }
+    }
+    pub fn metric() -> Self {
+        KitchenRounding {
+            units: vec![Unit::Liter, Unit::Milliliter],
+            tolerance: MixedRational::fract(1, 20),
+        }
+    }
+}
+
+impl Default for KitchenRounding {
+    fn default() -> Self {
+        Self::us()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RoundedMeasure {
+    /// Amounts to measure out, ex: 1/4 cup + 1 tbsp
+    pub parts: Vec<MeasureType>,
+    /// Rounded total minus the original quantity, in the smallest tool used
+    pub error: MeasureType,
+}
+
+impl std::fmt::Display for RoundedMeasure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts = self
+            .parts
+            .iter()
+            .map(|p| format!("{} {}", p.count, p.unit))
+            .collect::<Vec<_>>();
+        write!(f, "{}", parts.join(" + "))
+    }
+}
+
 #[allow(unused)]
 #[rustfmt::skip]
 // https://en.wikibooks.org/wiki/Cookbook:Units_of_measurement
 pub const STANDARD_COOKING_MEASUREMENT_UNITS: &[Measure] = &[
-    Measure {fluid: true, names: &["ml", "milliliter", "millilitre", "cc", "mL"], unit: Unit::Milliliter,  },
-    Measure {fluid: true, names: &["liter", "litre", "l", "L"], unit: Unit::Liter,},
-    Measure {fluid: true, names: &["dl", "deciliter", "decilitre", "dL"], unit: Unit::Deciliter,  },
-    Measure {fluid: false, names: &["tsp", "teaspoon", "t"], unit: Unit::Teaspoon,},
-    Measure {fluid: false, names: &["tbsp", "tablespoon", "tbs", "tbl", "T"], unit: Unit::Tablespoon,   },
-    Measure {fluid: true, names: &["fl oz", "fluid oz"], unit: Unit::FluidOunce,   },
-    Measure {fluid: false, names: &["gill"], unit: Unit::Gill,},
-    Measure {fluid: false, names: &["cup", "c"], unit: Unit::Cup,},
-    Measure {fluid: true, names: &["pint", "fl pt", "pt", "p"], unit: Unit::Pint,},
-    Measure {fluid: true, names: &["quart", "fl qt", "qt", "q"], unit: Unit::Quart,   },
-    Measure {fluid: true, names: &["gal", "gallon", "g"], unit: Unit::Gallon, },
-    Measure {fluid: false, names: &["mg", "milligram", "milligramme"], unit: Unit::Milligram, },
-    Measure {fluid: false, names: &["kg", "kilogram", "kilogramme"], unit: Unit::Kilogram,   },
-    Measure {fluid: false, names: &["g", "gram", "gramme"], unit: Unit::Gram,},
-    Measure {fluid: false, names: &["lb", "pound"], unit: Unit::Pound,},
-    Measure {fluid: false, names: &["oz", "ounce"], unit: Unit::Ounce,},
-
-    Measure {fluid: false, names: &["tad"], unit: Unit::Tad,},
-    Measure {fluid: false, names: &["dash"], unit: Unit::Dash,},
-    Measure {fluid: false, names: &["pinch"], unit: Unit::Pinch,},
-    Measure {fluid: false, names: &["smidgen"], unit: Unit::Smidgen,},
-    Measure {fluid: false, names: &["drop"], unit: Unit::Drop,},
+    Measure {fluid: true, names: &["ml", "milliliter", "millilitre", "cc", "mL"], unit: Unit::Milliliter, system: UnitType::Metric,},
+    Measure {fluid: true, names: &["liter", "litre", "l", "L"], unit: Unit::Liter, system: UnitType::Metric,},
+    Measure {fluid: true, names: &["dl", "deciliter", "decilitre", "dL"], unit: Unit::Deciliter, system: UnitType::Metric,},
+    Measure {fluid: false, names: &["tsp", "teaspoon", "t"], unit: Unit::Teaspoon, system: UnitType::UsCustomary,},
+    Measure {fluid: false, names: &["tbsp", "tablespoon", "tbs", "tbl", "T"], unit: Unit::Tablespoon, system: UnitType::UsCustomary,},
+    Measure {fluid: true, names: &["fl oz", "fluid oz"], unit: Unit::FluidOunce, system: UnitType::UsCustomary,},
+    Measure {fluid: false, names: &["gill"], unit: Unit::Gill, system: UnitType::UsCustomary,},
+    Measure {fluid: false, names: &["cup", "c"], unit: Unit::Cup, system: UnitType::UsCustomary,},
+    Measure {fluid: true, names: &["pint", "fl pt", "pt", "p"], unit: Unit::Pint, system: UnitType::UsCustomary,},
+    Measure {fluid: true, names: &["quart", "fl qt", "qt", "q"], unit: Unit::Quart, system: UnitType::UsCustomary,},
+    Measure {fluid: true, names: &["gal", "gallon", "g"], unit: Unit::Gallon, system: UnitType::UsCustomary,},
+    Measure {fluid: false, names: &["mg", "milligram", "milligramme"], unit: Unit::Milligram, system: UnitType::Metric,},
+    Measure {fluid: false, names: &["kg", "kilogram", "kilogramme"], unit: Unit::Kilogram, system: UnitType::Metric,},
+    Measure {fluid: false, names: &["g", "gram", "gramme"], unit: Unit::Gram, system: UnitType::Metric,},
+    Measure {fluid: false, names: &["lb", "pound"], unit: Unit::Pound, system: UnitType::UsCustomary,},
+    Measure {fluid: false, names: &["oz", "ounce"], unit: Unit::Ounce, system: UnitType::UsCustomary,},
+
+    Measure {fluid: false, names: &["tad"], unit: Unit::Tad, system: UnitType::Traditional,},
+    Measure {fluid: false, names: &["dash"], unit: Unit::Dash, system: UnitType::Traditional,},
+    Measure {fluid: false, names: &["pinch"], unit: Unit::Pinch, system: UnitType::Traditional,},
+    Measure {fluid: false, names: &["smidgen"], unit: Unit::Smidgen, system: UnitType::Traditional,},
+    Measure {fluid: false, names: &["drop"], unit: Unit::Drop, system: UnitType::Traditional,},
 
+    Measure {fluid: true, names: &["imp fl oz", "imperial fl oz", "imperial fluid ounce"], unit: Unit::ImperialFluidOunce, system: UnitType::Imperial,},
+    Measure {fluid: false, names: &["imp gill", "imperial gill"], unit: Unit::ImperialGill, system: UnitType::Imperial,},
+    Measure {fluid: true, names: &["imp pint", "imperial pint", "imp pt"], unit: Unit::ImperialPint, system: UnitType::Imperial,},
+    Measure {fluid: true, names: &["imp quart", "imperial quart", "imp qt"], unit: Unit::ImperialQuart, system: UnitType::Imperial,},
+    Measure {fluid: true, names: &["imp gal", "imperial gallon"], unit: Unit::ImperialGallon, system: UnitType::Imperial,},
+    Measure {fluid: false, names: &["legal cup"], unit: Unit::LegalCup, system: UnitType::UsLegal,},
+    Measure {fluid: true, names: &["legal fl oz", "legal fluid ounce"], unit: Unit::LegalFluidOunce, system: UnitType::UsLegal,},
+    Measure {fluid: false, names: &["metric cup"], unit: Unit::MetricCup, system: UnitType::Metric,},
+    Measure {fluid: false, names: &["metric tsp", "metric teaspoon"], unit: Unit::MetricTeaspoon, system: UnitType::Metric,},
+    Measure {fluid: false, names: &["metric tbsp", "metric tablespoon"], unit: Unit::MetricTablespoon, system: UnitType::Metric,},
+    Measure {fluid: false, names: &["au tbsp", "australian tablespoon"], unit: Unit::AustralianTablespoon, system: UnitType::Australian,},
+
+    Measure {fluid: false, names: &["can", "tin"], unit: Unit::Can, system: UnitType::Traditional,},
+    Measure {fluid: false, names: &["jar"], unit: Unit::Jar, system: UnitType::Traditional,},
+    Measure {fluid: false, names: &["bottle"], unit: Unit::Bottle, system: UnitType::Traditional,},
+    Measure {fluid: false, names: &["package", "pkg", "packet", "pack", "envelope"], unit: Unit::Package, system: UnitType::Traditional,},
+    Measure {fluid: false, names: &["box"], unit: Unit::Box, system: UnitType::Traditional,},
+    Measure {fluid: false, names: &["bag"], unit: Unit::Bag, system: UnitType::Traditional,},
+    Measure {fluid: false, names: &["stick"], unit: Unit::Stick, system: UnitType::Traditional,},
+    Measure {fluid: false, names: &["clove"], unit: Unit::Clove, system: UnitType::Traditional,},
+    Measure {fluid: false, names: &["bunch"], unit: Unit::Bunch, system: UnitType::Traditional,},
+    Measure {fluid: false, names: &["head"], unit: Unit::Head, system: UnitType::Traditional,},
+    Measure {fluid: false, names: &["sprig"], unit: Unit::Sprig, system: UnitType::Traditional,},
+    Measure {fluid: false, names: &["slice"], unit: Unit::Slice, system: UnitType::Traditional,},
+    Measure {fluid: false, names: &["piece", "pc"], unit: Unit::Piece, system: UnitType::Traditional,},
+    Measure {fluid: false, names: &["batch"], unit: Unit::Batch, system: UnitType::Traditional,},
+
+    Measure {fluid: false, names: &["°F", "fahrenheit"], unit: Unit::Fahrenheit, system: UnitType::UsCustomary,},
+    Measure {fluid: false, names: &["°C", "celsius", "centigrade"], unit: Unit::Celsius, system: UnitType::Metric,},
+    Measure {fluid: false, names: &["gas mark"], unit: Unit::GasMark, system: UnitType::Imperial,},
 ];
 
 impl Measure {
-    pub fn new(mut name: String) -> Self {
-        if !name.is_empty() {
-            for i in 0..5 {
-                for m in STANDARD_COOKING_MEASUREMENT_UNITS {
-                    if i >= m.names.len() {
-                        break;
-                    }
-                    if name.ends_with("'s") {
-                        name = name.replace("'s", "");
-                    }
-                    if name.ends_with('s') {
-                        name = name[..name.len() - 1].into();
-                    }
-                    if name == m.names[i] {
-                        return *m;
-                    }
-                }
-            }
-        }
-        Measure {
-            names: &[""],
-            unit: Unit::Other,
-            fluid: false,
-            //Country::None,
+    pub fn new(name: String) -> Self {
+        let name = name.trim().replace("'s", "");
+        let name = name.trim_end_matches('.');
+        // Try the name as written, then as a singular, ex: pinches, cups
+        if name.is_empty() {
+            return Measure::default();
         }
+        Measure::find(name, STANDARD_COOKING_MEASUREMENT_UNITS)
+            .or_else(|| Measure::find(name, &ConversionGraph::global().read().ok()?.custom))
+            .unwrap_or_default()
+    }
+    // The measure in `measures` called `name`, first names first
+    fn find(name: &str, measures: &[Measure]) -> Option<Measure> {
+        let candidates = [Some(name), name.strip_suffix("es"), name.strip_suffix('s')];
+        (0..5).find_map(|i| {
+            measures
+                .iter()
+                .find(|m| m.names.get(i).is_some_and(|n| candidates.iter().flatten().any(|c| c == n)))
+                .copied()
+        })
     }
     pub fn from_enum(unit: Unit) -> Self {
-        match unit {
-            Unit::Milliliter => STANDARD_COOKING_MEASUREMENT_UNITS[0],
-            Unit::Liter => STANDARD_COOKING_MEASUREMENT_UNITS[1],
-            Unit::Deciliter => STANDARD_COOKING_MEASUREMENT_UNITS[2],
-            Unit::Teaspoon => STANDARD_COOKING_MEASUREMENT_UNITS[3],
-            Unit::Tablespoon => STANDARD_COOKING_MEASUREMENT_UNITS[4],
-            Unit::FluidOunce => STANDARD_COOKING_MEASUREMENT_UNITS[5],
-            Unit::Gill => STANDARD_COOKING_MEASUREMENT_UNITS[6],
-            Unit::Cup => STANDARD_COOKING_MEASUREMENT_UNITS[7],
-            Unit::Pint => STANDARD_COOKING_MEASUREMENT_UNITS[8],
-            Unit::Quart => STANDARD_COOKING_MEASUREMENT_UNITS[9],
-            Unit::Gallon => STANDARD_COOKING_MEASUREMENT_UNITS[10],
-            Unit::Milligram => STANDARD_COOKING_MEASUREMENT_UNITS[11],
-            Unit::Kilogram => STANDARD_COOKING_MEASUREMENT_UNITS[12],
-            Unit::Gram => STANDARD_COOKING_MEASUREMENT_UNITS[13],
-            Unit::Pound => STANDARD_COOKING_MEASUREMENT_UNITS[14],
-            Unit::Ounce => STANDARD_COOKING_MEASUREMENT_UNITS[15],
-            _ => Measure {
-                names: &[""],
-                unit: Unit::Other,
-                fluid: false,
-                //Country::None,
-            },
-        }
+        STANDARD_COOKING_MEASUREMENT_UNITS
+            .iter()
+            .find(|m| m.unit == unit)
+            .copied()
+            .unwrap_or_default()
     }
-    #[allow(clippy::match_single_binding)]
+    /// How many of `a` are in one `b`, `convert` divides by this
     pub fn conversion_table(a: &Measure, b: &Measure) -> Option<MixedRational> {
-        use Unit::*;
-        let one = MixedRational::whole(1);
-        if a.unit as u8 == b.unit as u8 {
-            return Some(one);
-        }
-        // Return how many of A are in B
-        // or, B per A
-        match a.unit {
-            // Fluids
-            Milliliter => match b.unit {
-                Unit::Liter => Some(MixedRational::whole(1000)),
-                Unit::Deciliter => Some(MixedRational::whole(100)),
-                Unit::Teaspoon => Some(MixedRational::fract(157725491, 32000000).approx_ratio(2)),
-                Unit::Tablespoon => Some(MixedRational::fract(473176473, 32000000).approx_ratio(2)),
-                Unit::FluidOunce => Some(MixedRational::fract(473176473, 16000000).approx_ratio(2)),
-                Unit::Gill => Some(MixedRational::fract(473176473, 4000000).approx_ratio(2)),
-                Unit::Cup => Some(MixedRational::fract(473176473, 2000000).approx_ratio(2)),
-                Unit::Pint => Some(MixedRational::fract(473176473, 1000000).approx_ratio(2)),
-                Unit::Quart => Some(MixedRational::fract(473176473, 500000).approx_ratio(2)),
-                Unit::Gallon => Some(MixedRational::fract(473176473, 125000).approx_ratio(2)),
-                _ => None,
-            },
-            Teaspoon => match b.unit {
-                Unit::Milliliter => Some(MixedRational::fract(32000000, 157725491).approx_ratio(3)),
-                Unit::Liter => Some(MixedRational::new(202, 884136, 1000000).approx_ratio(2)),
-                Unit::Deciliter => Some(MixedRational::new(20, 2884136, 10000000).approx_ratio(2)),
-                Unit::Tablespoon => Some(MixedRational::whole(3)),
-                Unit::FluidOunce => Some(MixedRational::whole(6)),
-                Unit::Gill => Some(MixedRational::whole(24)),
-                Unit::Cup => Some(MixedRational::whole(48)),
-                Unit::Pint => Some(MixedRational::whole(96)),
-                Unit::Quart => Some(MixedRational::whole(192)),
-                Unit::Gallon => Some(MixedRational::whole(768)),
-                _ => None,
-            },
-            Tablespoon => match b.unit {
-                Unit::Milliliter => Some(MixedRational::fract(32000000, 473176473).approx_ratio(3)),
-                Unit::Liter => Some(MixedRational::new(67, 6280454, 10000000).approx_ratio(2)),
-                Unit::Deciliter => Some(MixedRational::new(6, 76280454, 100000000).approx_ratio(2)),
-                Unit::Tablespoon => Some(MixedRational::fract(1, 3)),
-                Unit::FluidOunce => Some(MixedRational::whole(2)),
-                Unit::Gill => Some(MixedRational::whole(8)),
-                Unit::Cup => Some(MixedRational::whole(16)),
-                Unit::Pint => Some(MixedRational::whole(32)),
-                Unit::Quart => Some(MixedRational::whole(64)),
-                Unit::Gallon => Some(MixedRational::whole(256)),
-                _ => None,
-            },
-            Cup => match b.unit {
-                Unit::Milliliter => Some(MixedRational::fract(2000000, 473176473).approx_ratio(2)),
-                Unit::Liter => Some(MixedRational::new(4, 22675284, 100000000).approx_ratio(2)),
-                Unit::Deciliter => Some(MixedRational::fract(200000000, 473176473).approx_ratio(2)),
-                Unit::Teaspoon => Some(MixedRational::fract(1, 48)),
-                Unit::Tablespoon => Some(MixedRational::fract(1, 16)),
-                Unit::FluidOunce => Some(MixedRational::fract(1, 8)),
-                Unit::Gill => Some(MixedRational::fract(1, 2)),
-                Unit::Pint => Some(MixedRational::whole(2)),
-                Unit::Quart => Some(MixedRational::whole(4)),
-                Unit::Gallon => Some(MixedRational::whole(16)),
-                _ => None,
-            },
-            // Dry
-            Gram => match b.unit {
-                Unit::Milligram => Some(MixedRational::fract(1, 1000)),
-                Unit::Kilogram => Some(MixedRational::whole(1000)),
-                Unit::Pound => Some(MixedRational::new(453, 74, 125).approx_ratio(2)),
-                Unit::Ounce => Some(MixedRational::new(28, 699, 2000).approx_ratio(2)),
-                _ => None,
-            },
-            Milligram => match b.unit {
-                Unit::Gram => Some(MixedRational::whole(1000)),
-                Unit::Kilogram => Some(MixedRational::whole(1000000)),
-                Unit::Pound => Some(MixedRational::whole(453592)),
-                Unit::Ounce => Some(MixedRational::new(28349, 1, 2)),
-                _ => None,
-            },
-            Kilogram => match b.unit {
-                Unit::Milligram => Some(MixedRational::fract(1, 1000000)),
-                Unit::Gram => Some(MixedRational::fract(1, 1000)),
-                Unit::Pound => Some(MixedRational::fract(56699, 125000).approx_ratio(3)),
-                Unit::Ounce => Some(MixedRational::fract(56699, 2000000).approx_ratio(5)),
-                _ => None,
-            },
-            Pound => match b.unit {
-                Unit::Milligram => Some(MixedRational::fract(551, 250000000)),
-                Unit::Gram => Some(MixedRational::fract(110231, 50000000).approx_ratio(2)),
-                Unit::Kilogram => Some(MixedRational::new(2, 10231, 50000).approx_ratio(2)),
-                Unit::Ounce => Some(MixedRational::fract(1, 16)),
-                _ => None,
-            },
-            Ounce => match b.unit {
-                Unit::Gram => Some(MixedRational::fract(17637, 500000).approx_ratio(3)),
-                Unit::Milligram => Some(MixedRational::fract(17637, 500000000)),
-                Unit::Kilogram => Some(MixedRational::new(35, 137, 500).approx_ratio(2)),
-                Unit::Pound => Some(MixedRational::whole(16)),
-                _ => None,
-            },
-            _ => None,
-        }
+        ConversionGraph::global().read().ok()?.factor(a, b)
     }
     #[allow(unused)]
     pub fn convert(&self, quantity: MixedRational, new_measure: Measure) -> Option<MixedRational> {
         Measure::conversion_table(self, &new_measure).map(|scale| quantity / scale)
     }
+    /// The regional version of this unit for a recipe written in `region`
+    pub fn in_region(&self, region: UnitType) -> Self {
+        let resolved = region.resolve(self.unit);
+        if resolved as u8 == self.unit as u8 {
+            *self
+        } else {
+            Measure::from_enum(resolved)
+        }
+    }
+    pub fn dimension(&self) -> Option<Dimension> {
+        ConversionGraph::global().read().ok()?.dimension(self)
+    }
+    pub fn is_volume(&self) -> bool {
+        self.dimension() == Some(Dimension::Volume)
+    }
+    pub fn is_mass(&self) -> bool {
+        self.dimension() == Some(Dimension::Mass)
+    }
+    /// Convert between any two units, `density` in grams per milliliter is needed
+    /// to go between volume and mass
     #[allow(unused)]
     pub fn convert_universal(
         &self,
@@ -434,28 +838,112 @@ impl Measure {
         new_measure: Measure,
         density: Option<MixedRational>,
     ) -> Option<MixedRational> {
-        if self.fluid == new_measure.fluid {
-            Measure::conversion_table(self, &new_measure).map(|scale| quantity / scale)
-        } else if let Some(d) = density {
-            if self.fluid {
-                if let Some(new_quantity) =
-                    self.convert(quantity, Measure::from_enum(Unit::FluidOunce))
-                {
-                    let ounces = new_quantity * MixedRational::new(1, 43176, 1000000) * d;
-                    self.convert(ounces, new_measure)
-                } else {
-                    None
+        let (milliliter, gram) = (Measure::from_enum(Unit::Milliliter), Measure::from_enum(Unit::Gram));
+        match (density, self.is_volume(), new_measure.is_mass()) {
+            (Some(d), true, true) => {
+                let grams = self.convert(quantity, milliliter)? * d;
+                gram.convert(grams, new_measure)
+            }
+            (Some(d), false, false) if self.is_mass() && new_measure.is_volume() && !d.is_zero() => {
+                let milliliters = self.convert(quantity, gram)? / d;
+                milliliter.convert(milliliters, new_measure)
+            }
+            _ => self.convert(quantity, new_measure),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RecipeItem;
+
+    #[test]
+    fn every_pair_round_trips() {
+        let graph = ConversionGraph::standard();
+        let measures = STANDARD_COOKING_MEASUREMENT_UNITS
+            .iter()
+            .filter(|m| graph.dimension(m).is_some())
+            .collect::<Vec<_>>();
+        let quantities = [MixedRational::whole(1), MixedRational::fract(3, 4), MixedRational::new(2, 1, 3)];
+        for a in measures.iter() {
+            for b in measures.iter().filter(|b| graph.dimension(b) == graph.dimension(a)) {
+                let (there, back) = (graph.factor(a, b).unwrap(), graph.factor(b, a).unwrap());
+                assert_eq!(there * back, MixedRational::whole(1), "{} and {}", a, b);
+                for q in quantities {
+                    assert_eq!(q / there / back, q, "{} {} to {} and back", q, a, b);
                 }
-            } else if let Some(new_quantity) =
-                self.convert(quantity, Measure::from_enum(Unit::Ounce))
-            {
-                let fluid_ounces = new_quantity / (MixedRational::new(1, 43176, 1000000) * d);
-                self.convert(fluid_ounces, new_measure)
-            } else {
-                None
             }
-        } else {
-            None
         }
     }
+
+    #[test]
+    fn register_new_unit_by_name() {
+        let knob = ConversionGraph::global()
+            .write()
+            .unwrap()
+            .register("knob", Dimension::Mass, MixedRational::whole(15000));
+        assert_eq!(knob.unit, Unit::Custom);
+        let parsed = Measure::new("knobs".into());
+        assert_eq!(parsed.names[0], "knob");
+        let grams = parsed.convert(MixedRational::whole(2), Measure::from_enum(Unit::Gram));
+        assert_eq!(grams, Some(MixedRational::whole(30)));
+        // Scaling keeps the unit the recipe used
+        let doubled = MeasureType { count: MixedRational::whole(2), unit: parsed } * MixedRational::whole(2);
+        assert_eq!((doubled.count, doubled.unit.names[0]), (MixedRational::whole(4), "knob"));
+    }
+
+    #[test]
+    fn register_size_of_existing_unit() {
+        let mut graph = ConversionGraph::standard();
+        let can = graph.register("cans", Dimension::Volume, MixedRational::whole(400));
+        assert_eq!(can.unit, Unit::Can);
+        let ml = Measure::from_enum(Unit::Milliliter);
+        assert_eq!(graph.factor(&ml, &can), Some(MixedRational::whole(400)));
+    }
+
+    #[test]
+    fn metric_results_are_decimals() {
+        let metric = |line: &str| RecipeItem::parse(line, None).unwrap().to_unit_system(UnitType::Metric).to_string();
+        assert_eq!(metric("1 lb flour").trim(), "454 grams flour");
+        assert_eq!(metric("1 cup milk").trim(), "237 milliliters milk");
+        assert_eq!(metric("1 gallon water").trim(), "3.8 liters water");
+        let uk_pint = RecipeItem::parse("1 pint milk", Some(UnitType::Imperial)).unwrap();
+        assert_eq!(uk_pint.measure.unit.unit, Unit::ImperialPint);
+        assert_eq!(uk_pint.to_unit_system(UnitType::Metric).to_string().trim(), "568 milliliters milk");
+        assert_eq!(metric("1-2 cups milk").trim(), "237-473 milliliters milk");
+        let kilos = MeasureType { count: MixedRational::fract(3, 2), unit: Measure::from_enum(Unit::Kilogram) };
+        let third = kilos * MixedRational::fract(1, 3);
+        assert_eq!((third.count, third.unit.unit), (MixedRational::whole(500), Unit::Gram));
+        let liters = MeasureType { count: MixedRational::fract(3, 2), unit: Measure::from_enum(Unit::Liter) };
+        let third = liters * MixedRational::fract(1, 3);
+        assert_eq!((third.count, third.unit.unit), (MixedRational::whole(500), Unit::Milliliter));
+    }
+
+    fn round(count: MixedRational, unit: Unit) -> RoundedMeasure {
+        MeasureType { count, unit: Measure::from_enum(unit) }.round_for_kitchen(&KitchenRounding::us())
+    }
+
+    #[test]
+    fn kitchen_rounding_uses_a_part_per_tool() {
+        assert_eq!(round(MixedRational::fract(5, 8), Unit::Cup).to_string(), "1/2 cup + 2 tbsp");
+        let rounded = round(MixedRational::whole(333), Unit::Milliliter);
+        assert_eq!(rounded.to_string(), "1 cup + 1/3 cup + 1 tbsp");
+        assert_eq!(rounded.error.unit.unit, Unit::Tablespoon);
+        assert!(rounded.error.count.den <= MAX_DENOMINATOR);
+    }
+
+    #[test]
+    fn kitchen_rounding_stays_within_tolerance() {
+        let exact = round(MixedRational::fract(3, 4), Unit::Cup);
+        assert_eq!(exact.to_string(), "3/4 cup");
+        assert!(exact.error.count.is_zero());
+        // 1 1/20 cup is within 5% of 1 cup, but not within 1%
+        let close = round(MixedRational::fract(21, 20), Unit::Cup);
+        assert_eq!(close.to_string(), "1 cup");
+        assert_eq!((close.error.count, close.error.unit.unit), (MixedRational::fract(-1, 20), Unit::Cup));
+        let strict = KitchenRounding { tolerance: MixedRational::fract(1, 100), ..KitchenRounding::us() };
+        let cup = MeasureType { count: MixedRational::fract(21, 20), unit: Measure::from_enum(Unit::Cup) };
+        assert_eq!(cup.round_for_kitchen(&strict).to_string(), "1 cup + 1/2 tbsp + 1 tsp");
+    }
 }
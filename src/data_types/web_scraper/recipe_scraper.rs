@@ -1,5 +1,4 @@
 use crate::{get_time_string, ParsedRecipe};
-use reqwest::header::Iter;
 use serde::de::IntoDeserializer;
 
 #[derive(Default)]
@@ -141,8 +140,9 @@ impl Scraper {
                                         name: item["name"].to_string().replace('"', ""),
                                         processed: false,
                                     });
-                                    self.parsed
-                                        .push(ParsedRecipe::from((url.clone(), item.clone())));
+                                    let mut recipe = ParsedRecipe::from((url.clone(), item.clone()));
+                                    recipe.data.group_ingredients_from_html(&document);
+                                    self.parsed.push(recipe);
                                 }
                             }
                         } else {
@@ -163,8 +163,9 @@ impl Scraper {
                                 name: json["name"].to_string().replace('"', ""),
                                 processed: false,
                             });
-                            self.parsed
-                                .push(ParsedRecipe::from((url.clone(), json.clone())));
+                            let mut recipe = ParsedRecipe::from((url.clone(), json.clone()));
+                            recipe.data.group_ingredients_from_html(&document);
+                            self.parsed.push(recipe);
                         }
                         // Parse to JSON and push to structs list of JSON
 
@@ -1,6 +1,6 @@
 #![allow(unused_imports)]
 #![allow(unused_variables)]
-use crate::{mixed_rational::MixedRational, recipe::ParsedRecipe, units::*};
+use crate::{link_nested_recipes, mixed_rational::MixedRational, recipe::ParsedRecipe, units::*};
 use serde_json::{Result, Value};
 use std::{error::Error, fs, path::PathBuf, time::SystemTime};
 use time::OffsetDateTime;
@@ -26,7 +26,7 @@ pub fn recursive_json_grab(
     println!(
         "{} Searching [{}/]:",
         padding.repeat(depth),
-        &dir.as_os_str().to_string_lossy().split('/').last().unwrap()
+        &dir.as_os_str().to_string_lossy().split('/').next_back().unwrap()
     );
     // Move found recipes to new vec
     let mut new_recipes = recipes;
@@ -55,6 +55,10 @@ pub fn recursive_json_grab(
             }
         }
     }
+    // Back at the top everything is loaded, so recipes can find the ones they use
+    if depth == 0 {
+        link_nested_recipes(&mut new_recipes);
+    }
     // Finally return our recipes :)
     Ok(new_recipes)
 }
@@ -16,6 +16,9 @@ use std::{error::Error, fs, path::PathBuf, str::FromStr};
 #[allow(clippy::never_loop)]
 fn main() -> core::result::Result<(), Box<dyn Error>> {
     let recipe_data = recursive_json_grab("recipes".into(), Vec::new(), 0, "     |")?;
+    for error in recipe_book::check_nested_recipes(&recipe_data) {
+        println!("{}", error);
+    }
 
     for recipe in recipe_data {
         println!("{}", recipe);